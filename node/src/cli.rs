@@ -1,4 +1,11 @@
-use sc_cli::RunCmd;
+use crate::{
+	health::HealthConfig,
+	service::{BackoffConfig, ExtraConfig},
+};
+use std::{
+	net::{Ipv4Addr, SocketAddr},
+	path::PathBuf,
+};
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -10,6 +17,102 @@ pub struct Cli {
 	pub run: RunCmd,
 }
 
+/// The `sc-cli` run command, with the options specific to this node.
+#[derive(Debug, StructOpt)]
+pub struct RunCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub base: sc_cli::RunCmd,
+
+	/// Run the GRANDPA observer instead of the full voter.
+	///
+	/// Observers follow finality from the commit messages of the authorities without taking part
+	/// in vote gossip. Only allowed on non-authority full nodes.
+	#[structopt(long)]
+	pub grandpa_observer: bool,
+
+	/// Slow down block authoring when finality lags behind the best block.
+	///
+	/// Once more than `--backoff-unfinalized-slack` blocks are unfinalized, authorities skip an
+	/// increasing number of their slots, up to `--backoff-max-interval` slots between blocks.
+	#[structopt(long)]
+	pub backoff_authoring: bool,
+
+	/// Number of unfinalized blocks that are authored at full speed. Defaults to 50.
+	// No `default_value`: clap counts a default as given, which would always trigger `requires`.
+	#[structopt(long, value_name = "BLOCKS", requires = "backoff-authoring")]
+	pub backoff_unfinalized_slack: Option<u32>,
+
+	/// Maximum number of slots skipped between two blocks while backing off. Defaults to 100.
+	#[structopt(long, value_name = "SLOTS", requires = "backoff-authoring")]
+	pub backoff_max_interval: Option<u32>,
+
+	/// Serve `/health`, `/ready` and `/authoring` over HTTP on this port.
+	#[structopt(long, value_name = "PORT")]
+	pub health_port: Option<u16>,
+
+	/// Listen to all interfaces for the health server, instead of only on localhost.
+	#[structopt(long)]
+	pub health_external: bool,
+
+	/// Number of blocks the node may be behind the network for `/ready` to succeed.
+	#[structopt(long, default_value = "10")]
+	pub ready_max_sync_lag: u32,
+
+	/// Number of unfinalized blocks allowed for `/ready` to succeed.
+	#[structopt(long, default_value = "50")]
+	pub ready_max_finality_lag: u32,
+
+	/// Save the pending transactions on shutdown and submit them again on start.
+	///
+	/// The transactions are written to the `txpool` file of the chain directory and revalidated
+	/// against the best block when the node starts again.
+	#[structopt(long)]
+	pub persist_txpool: bool,
+
+	/// Maximum size of the saved transactions, in MiB.
+	#[structopt(long, value_name = "MiB", default_value = "16")]
+	pub persist_txpool_max_size: usize,
+
+	/// Index the extrinsics and events of the best chain in the SQLite database at this path.
+	///
+	/// The index is queried with the `indexer_extrinsics` and `indexer_events` RPC methods.
+	#[structopt(long, value_name = "PATH")]
+	pub indexer: Option<PathBuf>,
+}
+
+impl RunCmd {
+	/// Configuration of the service that is not part of the `sc-service` configuration.
+	pub fn extra_config(&self) -> ExtraConfig {
+		ExtraConfig {
+			grandpa_observer: self.grandpa_observer,
+			backoff_authoring: if self.backoff_authoring {
+				Some(BackoffConfig {
+					unfinalized_slack: self.backoff_unfinalized_slack.unwrap_or(50),
+					max_interval: self.backoff_max_interval.unwrap_or(100),
+				})
+			} else {
+				None
+			},
+			health: self.health_port.map(|port| {
+				let ip =
+					if self.health_external { Ipv4Addr::UNSPECIFIED } else { Ipv4Addr::LOCALHOST };
+				HealthConfig {
+					addr: SocketAddr::new(ip.into(), port),
+					max_sync_lag: self.ready_max_sync_lag,
+					max_finality_lag: self.ready_max_finality_lag,
+				}
+			}),
+			persist_pool: if self.persist_txpool {
+				Some(self.persist_txpool_max_size * 1024 * 1024)
+			} else {
+				None
+			},
+			indexer: self.indexer.clone(),
+		}
+	}
+}
+
 #[derive(Debug, StructOpt)]
 pub enum Subcommand {
 	/// Key management cli utilities
@@ -21,13 +124,13 @@ pub enum Subcommand {
 	CheckBlock(sc_cli::CheckBlockCmd),
 
 	/// Export blocks.
-	ExportBlocks(sc_cli::ExportBlocksCmd),
+	ExportBlocks(crate::commands::ExportBlocksCmd),
 
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
 	/// Import blocks.
-	ImportBlocks(sc_cli::ImportBlocksCmd),
+	ImportBlocks(crate::commands::ImportBlocksCmd),
 
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
@@ -38,4 +141,42 @@ pub enum Subcommand {
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+
+	/// Try some command against runtime state.
+	#[cfg(feature = "try-runtime")]
+	TryRuntime(try_runtime_cli::TryRuntimeCmd),
+
+	/// Try some command against runtime state. Note: `try-runtime` feature must be enabled.
+	#[cfg(not(feature = "try-runtime"))]
+	TryRuntime,
+
+	/// Convert the state of a chain spec into a `try-runtime` snapshot file.
+	CreateSnapshot(crate::commands::CreateSnapshotCmd),
+
+	/// Turn the exported state of a chain into a bootable development chain spec.
+	ForkOff(crate::commands::ForkOffCmd),
+
+	/// Compare the metadata of the runtime with a baseline and check the version bumps.
+	CheckMetadata(crate::commands::CheckMetadataCmd),
+
+	/// Write the metadata of the runtime as SCALE, JSON and TypeScript type definitions.
+	ExportMetadata(crate::commands::ExportMetadataCmd),
+
+	/// Sign a call, submit it to a running node and watch it until finalized.
+	Tx(crate::commands::TxCmd),
+
+	/// Read a storage item by its pallet and name, and print it decoded.
+	Query(crate::commands::QueryCmd),
+
+	/// List the storage added, removed and modified between two blocks.
+	StateDiff(crate::commands::StateDiffCmd),
+
+	/// Re-execute a block and report its storage accesses and execution time per extrinsic.
+	ReplayBlock(crate::commands::ReplayBlockCmd),
+
+	/// Measure the execution and import time of full blocks against the block weight limits.
+	BenchmarkBlock(crate::commands::BenchmarkBlockCmd),
+
+	/// Submit signed extrinsics to running nodes at a target rate and report their inclusion.
+	Load(crate::commands::LoadCmd),
 }
@@ -125,12 +125,79 @@ pub fn run() -> sc_cli::Result<()> {
 				     `--features runtime-benchmarks`."
 					.into())
 			},
+		#[cfg(feature = "try-runtime")]
+		Some(Subcommand::TryRuntime(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				// we don't need any of the components of new_partial, just a runtime, or a task
+				// manager to do `async_run`.
+				let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
+				let task_manager =
+					sc_service::TaskManager::new(config.task_executor.clone(), registry)
+						.map_err(|e| sc_cli::Error::Service(sc_service::Error::Prometheus(e)))?;
+
+				Ok((cmd.run::<Block, service::Executor>(config), task_manager))
+			})
+		},
+		#[cfg(not(feature = "try-runtime"))]
+		Some(Subcommand::TryRuntime) => Err("TryRuntime wasn't enabled when building the node. \
+			 You can enable it with `--features try-runtime`."
+			.into()),
+		Some(Subcommand::CreateSnapshot(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config.chain_spec))
+		},
+		Some(Subcommand::ForkOff(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config.chain_spec))
+		},
+		Some(Subcommand::CheckMetadata(cmd)) => cmd.run(),
+		Some(Subcommand::ExportMetadata(cmd)) => cmd.run(),
+		Some(Subcommand::Tx(cmd)) => cmd.run(),
+		Some(Subcommand::Query(cmd)) if cmd.offline => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
+				Ok((cmd.run_offline(client), task_manager))
+			})
+		},
+		Some(Subcommand::Query(cmd)) => cmd.run(),
+		Some(Subcommand::StateDiff(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
+				Ok((cmd.run(client), task_manager))
+			})
+		},
+		Some(Subcommand::ReplayBlock(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
+				Ok((cmd.run(client), task_manager))
+			})
+		},
+		Some(Subcommand::BenchmarkBlock(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|mut config| {
+				let PartialComponents { client, task_manager, .. } = service::new_partial(&config)?;
+				// Keep the temporary database until the benchmark is done.
+				let base_path = config.base_path.take();
+				let benchmark = async move {
+					let result = cmd.run(client).await;
+					drop(base_path);
+					result
+				};
+				Ok((benchmark, task_manager))
+			})
+		},
+		Some(Subcommand::Load(cmd)) => cmd.run(),
 		None => {
-			let runner = cli.create_runner(&cli.run)?;
+			let runner = cli.create_runner(&cli.run.base)?;
+			let extra_config = cli.run.extra_config();
 			runner.run_node_until_exit(|config| async move {
 				match config.role {
 					Role::Light => service::new_light(config),
-					_ => service::new_full(config),
+					_ => service::new_full(config, extra_config),
 				}
 				.map_err(sc_cli::Error::Service)
 			})
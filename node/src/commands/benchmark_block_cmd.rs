@@ -0,0 +1,268 @@
+use super::extrinsics::{signed, Filler};
+use crate::service::{FullBackend, FullClient};
+use codec::{Decode, Encode};
+use node_template_runtime::{
+	opaque::Block, BlockWeights, Call, Index, TimestampCall, UncheckedExtrinsic, SLOT_DURATION,
+	VERSION, WEIGHT_PER_SECOND,
+};
+use sc_block_builder::BlockBuilderProvider;
+use sc_cli::{CliConfiguration, ImportParams, Result, SharedParams};
+use sc_client_api::StorageProvider;
+use sc_consensus::{BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult};
+use sc_service::BasePath;
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::{ApplyExtrinsicFailed, Error as ClientError, HeaderBackend};
+use sp_consensus::BlockOrigin;
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use sp_core::{
+	hashing::{blake2_256, twox_128},
+	sr25519,
+	storage::StorageKey,
+	ExecutionContext, Pair,
+};
+use sp_runtime::{
+	generic::{BlockId, Digest, DigestItem},
+	traits::Block as BlockT,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	AccountId32, OpaqueExtrinsic,
+};
+use std::{
+	collections::HashMap,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use structopt::StructOpt;
+
+/// The `benchmark-block` command used to measure how long full blocks take to execute and import.
+///
+/// Blocks are filled with `Balances::transfer` or `TemplateModule::do_something` signed by
+/// `//Alice` until `BlockWeights` or `BlockLength` reject any further extrinsic, and imported into
+/// a temporary database created from `--chain`. Each transfer goes to a new account, the most
+/// expensive case. Every block is executed, then imported, and both times are reported next to the
+/// weight the block consumed: the execution time is checked against `BlockWeights::max_block`, the
+/// compute budget of a block, and the import time against the slot duration.
+///
+/// Use `--execution Wasm` to measure the runtime the chain actually runs.
+#[derive(Debug, StructOpt)]
+pub struct BenchmarkBlockCmd {
+	/// Extrinsics to fill the blocks with, `transfer` or `do-something`. Defaults to both, one
+	/// after the other.
+	#[structopt(long = "extrinsic", value_name = "EXTRINSIC")]
+	pub extrinsics: Vec<Filler>,
+
+	/// Number of blocks to build for each kind of extrinsic.
+	#[structopt(long, default_value = "3")]
+	pub blocks: u32,
+
+	/// Stop filling a block after this many extrinsics, to benchmark partial blocks.
+	#[structopt(long)]
+	pub max_extrinsics: Option<usize>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+/// Measurements of a benchmarked block.
+struct Measurement {
+	extrinsics: usize,
+	/// Total weight consumed by the block, as recorded in `System::BlockWeight`.
+	weight: u64,
+	execution: Duration,
+	import: Duration,
+}
+
+impl BenchmarkBlockCmd {
+	/// Run the benchmark-block command
+	pub async fn run(&self, client: Arc<FullClient>) -> Result<()> {
+		let signer =
+			sr25519::Pair::from_string("//Alice", None).expect("static values are valid; qed");
+		let fillers = if self.extrinsics.is_empty() {
+			vec![Filler::Transfer, Filler::DoSomething]
+		} else {
+			self.extrinsics.clone()
+		};
+		let weights = BlockWeights::get();
+		let budget = weight_to_time(weights.max_block);
+		let slot = Duration::from_millis(SLOT_DURATION);
+		let normal = weights.per_class.normal.max_total.unwrap_or(weights.max_block);
+		println!(
+			"Budget: {} of compute (weight {}), {} of it for normal dispatches, {} slot",
+			ms(budget),
+			weights.max_block,
+			ms(weight_to_time(normal)),
+			ms(slot),
+		);
+
+		let mut nonce = 0;
+		let mut exceeded = false;
+		for filler in fillers {
+			let mut measurements = Vec::new();
+			for _ in 0..self.blocks {
+				let measurement = self.benchmark(&client, &signer, &mut nonce, filler).await?;
+				println!(
+					"{} #{}: {} extrinsics, weight {} ({}), executed in {}, imported in {}",
+					filler.name(),
+					client.info().best_number,
+					measurement.extrinsics,
+					measurement.weight,
+					ms(weight_to_time(measurement.weight)),
+					ms(measurement.execution),
+					ms(measurement.import),
+				);
+				measurements.push(measurement);
+			}
+
+			let max_execution = measurements.iter().map(|m| m.execution).max().unwrap_or_default();
+			let max_import = measurements.iter().map(|m| m.import).max().unwrap_or_default();
+			let weight_time =
+				measurements.iter().map(|m| weight_to_time(m.weight)).sum::<Duration>();
+			let execution = measurements.iter().map(|m| m.execution).sum::<Duration>();
+			println!(
+				"{}: execution takes {:.1}% of the time of the weights, up to {} of the {} budget",
+				filler.name(),
+				100.0 * execution.as_secs_f64() / weight_time.as_secs_f64(),
+				ms(max_execution),
+				ms(budget),
+			);
+			if max_execution <= budget && max_import <= slot {
+				println!("{}: blocks stay within the {} slot", filler.name(), ms(slot));
+			} else {
+				exceeded = true;
+				println!(
+					"{}: blocks EXCEED their budget, execution up to {}, import up to {}",
+					filler.name(),
+					ms(max_execution),
+					ms(max_import)
+				);
+			}
+		}
+
+		if exceeded {
+			return Err("Some blocks exceed their budget".into())
+		}
+		Ok(())
+	}
+
+	/// Build a block full of `filler` on top of the best block, execute and import it.
+	async fn benchmark(
+		&self,
+		client: &FullClient,
+		signer: &sr25519::Pair,
+		nonce: &mut Index,
+		filler: Filler,
+	) -> Result<Measurement> {
+		let info = client.info();
+		let parent = BlockId::Hash(info.best_hash);
+		let number = info.best_number + 1;
+
+		// Aura expects a new slot in every block, and the timestamp to be in that slot.
+		let slot = Slot::from(number as u64);
+		let digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+		let mut builder = client.new_block_at(&parent, digest, false)?;
+		let timestamp = Call::Timestamp(TimestampCall::set(number as u64 * SLOT_DURATION));
+		builder.push(opaque(UncheckedExtrinsic::new_unsigned(timestamp)))?;
+
+		let mut extrinsics = 0;
+		while self.max_extrinsics.map_or(true, |max| extrinsics < max) {
+			// Each transfer goes to a new account.
+			let dest = AccountId32::from(blake2_256(&(b"benchmark-block", *nonce).encode()));
+			let call = filler.call(*nonce, dest);
+			let xt = signed(signer, *nonce, info.genesis_hash, &VERSION, call);
+			match builder.push(opaque(xt)) {
+				Ok(()) => (),
+				Err(e) if is_full(&e) => break,
+				Err(e) =>
+					return Err(format!(
+						"Unable to push {} to block #{}: {}",
+						filler.name(),
+						number,
+						e
+					)
+					.into()),
+			}
+			*nonce += 1;
+			extrinsics += 1;
+		}
+		if extrinsics == 0 && self.max_extrinsics != Some(0) {
+			return Err(format!("No {} fits in block #{}", filler.name(), number).into())
+		}
+		let block = builder.build()?.block;
+
+		let start = Instant::now();
+		client
+			.runtime_api()
+			.execute_block_with_context(&parent, ExecutionContext::Importing, block.clone())
+			.map_err(|e| format!("Unable to execute block #{}: {}", number, e))?;
+		let execution = start.elapsed();
+
+		let hash = block.hash();
+		let (header, body) = block.deconstruct();
+		let mut params = BlockImportParams::new(BlockOrigin::File, header);
+		params.body = Some(body);
+		params.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+		let start = Instant::now();
+		let mut importer = client;
+		match importer.import_block(params, HashMap::new()).await {
+			Ok(ImportResult::Imported(_)) => (),
+			Ok(result) =>
+				return Err(format!("Block #{} not imported: {:?}", number, result).into()),
+			Err(e) => return Err(format!("Unable to import block #{}: {}", number, e).into()),
+		}
+		let import = start.elapsed();
+
+		let key = [twox_128(b"System"), twox_128(b"BlockWeight")].concat();
+		let consumed = StorageProvider::<Block, FullBackend>::storage(
+			client,
+			&BlockId::Hash(hash),
+			&StorageKey(key),
+		)?
+		.and_then(|weight| frame_system::ConsumedWeight::decode(&mut &weight.0[..]).ok())
+		.unwrap_or_default();
+		let weight = consumed.normal + consumed.operational + consumed.mandatory;
+
+		Ok(Measurement { extrinsics, weight, execution, import })
+	}
+}
+
+impl CliConfiguration for BenchmarkBlockCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+
+	fn base_path(&self) -> Result<Option<BasePath>> {
+		Ok(Some(BasePath::new_temp_dir()?))
+	}
+}
+
+/// Whether pushing an extrinsic failed because the block is full: `CheckWeight` rejects
+/// extrinsics exceeding the weight or length left in the block.
+fn is_full(error: &ClientError) -> bool {
+	matches!(
+		error,
+		ClientError::ApplyExtrinsicFailed(ApplyExtrinsicFailed::Validity(
+			TransactionValidityError::Invalid(InvalidTransaction::ExhaustsResources)
+		))
+	)
+}
+
+fn opaque(xt: UncheckedExtrinsic) -> OpaqueExtrinsic {
+	OpaqueExtrinsic::decode(&mut &xt.encode()[..]).expect("encoded extrinsics are valid; qed")
+}
+
+/// Time a weight accounts for.
+fn weight_to_time(weight: u64) -> Duration {
+	Duration::from_secs_f64(weight as f64 / WEIGHT_PER_SECOND as f64)
+}
+
+fn ms(duration: Duration) -> String {
+	format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,287 @@
+use crate::metadata::{self, Metadata, PalletMetadata, StorageMetadata};
+use codec::{Decode, Encode};
+use sc_cli::{Error, Result};
+use sp_version::RuntimeVersion;
+use std::{fmt::Debug, path::PathBuf};
+use structopt::StructOpt;
+
+/// First bytes of a Wasm module.
+const WASM_MAGIC: &[u8] = b"\0asm";
+/// First bytes of a zstd compressed runtime, see `sp-maybe-compressed-blob`.
+const ZSTD_PREFIX: &[u8] = &[82, 188, 83, 118, 70, 219, 142, 5];
+
+/// The `check-metadata` command used to catch runtime changes that require a version bump.
+///
+/// The metadata of the runtime compiled into this node is compared with a baseline, either an
+/// earlier Wasm runtime or a snapshot written with `--save`. Every added (`+`), removed (`-`) and
+/// changed (`~`) item is reported, and the command fails if:
+///
+/// - anything changed but `spec_version` was not bumped,
+/// - the encoding of calls or extrinsics changed but `transaction_version` was not bumped.
+#[derive(Debug, StructOpt)]
+pub struct CheckMetadataCmd {
+	/// Wasm runtime, compressed or not, or snapshot file to compare this runtime with.
+	#[structopt(long, parse(from_os_str))]
+	pub baseline: Option<PathBuf>,
+
+	/// Write the version and metadata of this runtime to a snapshot file.
+	#[structopt(long, parse(from_os_str))]
+	pub save: Option<PathBuf>,
+}
+
+impl CheckMetadataCmd {
+	/// Run the check-metadata command
+	pub fn run(&self) -> Result<()> {
+		if self.baseline.is_none() && self.save.is_none() {
+			return Err("Nothing to do, pass `--baseline` and/or `--save`.".into())
+		}
+
+		let version = node_template_runtime::VERSION;
+		let encoded = metadata::native_encoded();
+		// Read the baseline first, it may be the snapshot that is about to be overwritten.
+		let baseline = match &self.baseline {
+			Some(path) => Some(load_baseline(&std::fs::read(path)?)?),
+			None => None,
+		};
+
+		if let Some(path) = &self.save {
+			std::fs::write(path, (&version, &encoded).encode())?;
+		}
+
+		let (baseline_version, baseline) = match baseline {
+			Some(baseline) => baseline,
+			None => return Ok(()),
+		};
+		let diff = Diff::new(&Metadata::decode(&baseline)?, &Metadata::decode(&encoded)?);
+
+		for line in &diff.lines {
+			println!("{}", line);
+		}
+		println!("{} change(s), {} of which break transactions.", diff.lines.len(), diff.breaking);
+
+		check_versions(&baseline_version, &version, &diff).map_err(Error::Input)
+	}
+}
+
+/// Read a baseline, either a Wasm runtime or a snapshot written by `--save`.
+fn load_baseline(raw: &[u8]) -> Result<(RuntimeVersion, Vec<u8>)> {
+	if raw.starts_with(WASM_MAGIC) || raw.starts_with(ZSTD_PREFIX) {
+		Ok(metadata::from_wasm(raw)?)
+	} else {
+		<(RuntimeVersion, Vec<u8>)>::decode(&mut &raw[..]).map_err(|e| {
+			format!("Baseline is neither a Wasm runtime nor a snapshot: {:?}", e).into()
+		})
+	}
+}
+
+/// Check that the versions were bumped as required by `diff`.
+fn check_versions(
+	baseline: &RuntimeVersion,
+	current: &RuntimeVersion,
+	diff: &Diff,
+) -> std::result::Result<(), String> {
+	if baseline.spec_name != current.spec_name {
+		return Err(format!(
+			"The baseline is a `{}` runtime, not a `{}` one.",
+			baseline.spec_name, current.spec_name
+		))
+	}
+	if current.spec_version < baseline.spec_version ||
+		current.transaction_version < baseline.transaction_version
+	{
+		return Err("Versions are lower than the ones of the baseline.".into())
+	}
+	if !diff.lines.is_empty() && current.spec_version == baseline.spec_version {
+		return Err(format!(
+			"The metadata changed, `spec_version` must be bumped from {}.",
+			baseline.spec_version
+		))
+	}
+	if diff.breaking > 0 && current.transaction_version == baseline.transaction_version {
+		return Err(format!(
+			"The encoding of transactions changed, `transaction_version` must be bumped from {}.",
+			baseline.transaction_version
+		))
+	}
+	Ok(())
+}
+
+/// Differences between two metadata, documentation excluded.
+#[derive(Default)]
+struct Diff {
+	lines: Vec<String>,
+	/// Number of changes that invalidate transactions signed for the baseline.
+	breaking: usize,
+}
+
+impl Diff {
+	fn new(old: &Metadata, new: &Metadata) -> Self {
+		let mut diff = Diff::default();
+
+		if old.extrinsic_version != new.extrinsic_version {
+			diff.push(
+				format!(
+					"~ extrinsic version: {} -> {}",
+					old.extrinsic_version, new.extrinsic_version
+				),
+				true,
+			);
+		}
+		if old.signed_extensions != new.signed_extensions {
+			diff.push(
+				format!(
+					"~ signed extensions: {:?} -> {:?}",
+					old.signed_extensions, new.signed_extensions
+				),
+				true,
+			);
+		}
+
+		for old_pallet in &old.pallets {
+			match new.pallet(&old_pallet.name) {
+				Some(new_pallet) => diff.pallet(old_pallet, new_pallet),
+				None =>
+					diff.push(format!("- pallet {}", old_pallet.name), !old_pallet.calls.is_empty()),
+			}
+		}
+		for new_pallet in &new.pallets {
+			if old.pallet(&new_pallet.name).is_none() {
+				diff.push(format!("+ pallet {}", new_pallet.name), false);
+			}
+		}
+
+		diff
+	}
+
+	fn push(&mut self, line: String, breaking: bool) {
+		self.lines.push(line);
+		if breaking {
+			self.breaking += 1;
+		}
+	}
+
+	fn pallet(&mut self, old: &PalletMetadata, new: &PalletMetadata) {
+		let name = &old.name;
+
+		if old.index != new.index {
+			let breaking = !old.calls.is_empty();
+			self.push(format!("~ pallet {}: index {} -> {}", name, old.index, new.index), breaking);
+		}
+		if old.storage_prefix != new.storage_prefix {
+			self.push(
+				format!(
+					"~ pallet {}: storage prefix {} -> {}",
+					name, old.storage_prefix, new.storage_prefix
+				),
+				false,
+			);
+		}
+
+		let call = |c: &metadata::CallMetadata| {
+			let arguments: Vec<_> =
+				c.arguments.iter().map(|(name, ty)| format!("{}: {}", name, ty)).collect();
+			format!("({})", arguments.join(", "))
+		};
+		self.items(name, "call", &old.calls, &new.calls, |c| &c.name, call, true, true);
+
+		let event = |e: &metadata::EventMetadata| format!("({})", e.arguments.join(", "));
+		self.items(name, "event", &old.events, &new.events, |e| &e.name, event, true, false);
+
+		self.items(name, "storage", &old.storage, &new.storage, |s| &s.name, storage, false, false);
+
+		let constant = |c: &metadata::ConstantMetadata| {
+			format!(": {} = 0x{}", c.ty, sp_core::hexdisplay::HexDisplay::from(&c.value))
+		};
+		self.items(
+			name,
+			"constant",
+			&old.constants,
+			&new.constants,
+			|c| &c.name,
+			constant,
+			false,
+			false,
+		);
+
+		self.items(
+			name,
+			"error",
+			&old.errors,
+			&new.errors,
+			|e| &e.name,
+			|_| String::new(),
+			true,
+			false,
+		);
+	}
+
+	/// Compare the items of a pallet by name.
+	///
+	/// `signature` describes everything that matters about an item but its name. Items that are
+	/// `indexed` are encoded by their position, so moving them is a change as well. Changes to
+	/// items that are `in_transactions` break the transactions signed for the baseline.
+	#[allow(clippy::too_many_arguments)]
+	fn items<T>(
+		&mut self,
+		pallet: &str,
+		kind: &str,
+		old: &[T],
+		new: &[T],
+		name: impl Fn(&T) -> &String,
+		signature: impl Fn(&T) -> String,
+		indexed: bool,
+		in_transactions: bool,
+	) {
+		for (old_index, old_item) in old.iter().enumerate() {
+			let item = name(old_item);
+			let new_item = new.iter().enumerate().find(|(_, new_item)| name(new_item) == item);
+
+			match new_item {
+				Some((new_index, new_item)) => {
+					let (old_signature, new_signature) = (signature(old_item), signature(new_item));
+					if old_signature != new_signature {
+						self.push(
+							format!(
+								"~ {} {}::{}{} -> {}{}",
+								kind, pallet, item, old_signature, item, new_signature
+							),
+							in_transactions,
+						);
+					}
+					if indexed && old_index != new_index {
+						self.push(
+							format!(
+								"~ {} {}::{}: index {} -> {}",
+								kind, pallet, item, old_index, new_index
+							),
+							in_transactions,
+						);
+					}
+				},
+				None => self.push(
+					format!("- {} {}::{}{}", kind, pallet, item, signature(old_item)),
+					in_transactions,
+				),
+			}
+		}
+
+		for new_item in new {
+			let item = name(new_item);
+			if !old.iter().any(|old_item| name(old_item) == item) {
+				self.push(format!("+ {} {}::{}{}", kind, pallet, item, signature(new_item)), false);
+			}
+		}
+	}
+}
+
+fn storage(item: &StorageMetadata) -> String {
+	let keys: Vec<_> =
+		item.keys.iter().map(|(hasher, ty)| format!("{:?}({})", hasher, ty)).collect();
+	format!(
+		": {:?} [{}] -> {} (default 0x{})",
+		item.modifier,
+		keys.join(", "),
+		item.value,
+		sp_core::hexdisplay::HexDisplay::from(&item.default),
+	)
+}
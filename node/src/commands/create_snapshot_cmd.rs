@@ -0,0 +1,50 @@
+use codec::Encode;
+use sc_cli::{CliConfiguration, Result, SharedParams};
+use sc_service::ChainSpec;
+use sp_core::storage::{StorageData, StorageKey};
+use std::{fmt::Debug, path::PathBuf};
+use structopt::StructOpt;
+
+/// The `create-snapshot` command used to turn the state of a chain spec into a `try-runtime`
+/// snapshot.
+///
+/// The chain spec given with `--chain` is usually the output of `export-state`. The resulting
+/// file can be fed to `try-runtime <command> snap --snapshot-path <file>`.
+#[derive(Debug, StructOpt)]
+pub struct CreateSnapshotCmd {
+	/// Path of the snapshot file to write.
+	#[structopt(long, short = "o", parse(from_os_str))]
+	pub output: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl CreateSnapshotCmd {
+	/// Run the create-snapshot command
+	pub fn run(&self, spec: Box<dyn ChainSpec>) -> Result<()> {
+		let storage = spec.as_storage_builder().build_storage()?;
+
+		// `try-runtime` snapshots only carry the top trie.
+		if !storage.children_default.is_empty() {
+			return Err("Chain specs with child storage cannot be turned into a snapshot.".into())
+		}
+
+		let pairs = storage
+			.top
+			.into_iter()
+			.map(|(key, value)| (StorageKey(key), StorageData(value)))
+			.collect::<Vec<_>>();
+
+		std::fs::write(&self.output, pairs.encode())?;
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for CreateSnapshotCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
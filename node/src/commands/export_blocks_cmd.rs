@@ -0,0 +1,218 @@
+use crate::{
+	decoder::{events_key, DecodedEvent, DecodedExtrinsic, Decoder},
+	dispatch_error::DecodedError,
+	metadata::MetadataCache,
+	service::{FullBackend, FullClient},
+};
+use codec::Encode;
+use node_template_runtime::{opaque::Block, BlockNumber, Hash};
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, Result, SharedParams};
+use sc_client_api::{BlockBackend, StorageProvider};
+use sc_service::config::DatabaseConfig;
+use serde::{Deserialize, Serialize};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::blake2_256, storage::StorageKey, Bytes};
+use sp_runtime::{generic::BlockId, traits::Header as _};
+use std::{
+	fs::File,
+	io::{self, BufWriter, Write},
+	sync::Arc,
+};
+use structopt::StructOpt;
+
+/// The `export-blocks` command of `sc-cli`, with a mode exporting decoded blocks.
+///
+/// With `--decoded`, every block is written as one line of JSON: its extrinsics are decoded into
+/// calls with their signer, nonce and tip, and the block carries its events and justifications.
+/// The SCALE encoded header and extrinsics are kept too, so that `import-blocks --decoded` can
+/// import the blocks again.
+///
+/// Blocks are decoded with the metadata of the runtime that built them. If the state of the
+/// parent block was pruned, the metadata of the best block is used instead and the block is
+/// exported without its events.
+#[derive(Debug, StructOpt)]
+pub struct ExportBlocksCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub base: sc_cli::ExportBlocksCmd,
+
+	/// Export the blocks as JSON lines, with their extrinsics and events decoded.
+	#[structopt(long, conflicts_with = "binary")]
+	pub decoded: bool,
+}
+
+/// A block with its extrinsics and events decoded, one line of `export-blocks --decoded`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedBlock {
+	/// Number of the block.
+	pub number: BlockNumber,
+	/// Hash of the block.
+	pub hash: Hash,
+	/// Hash of the parent block.
+	pub parent_hash: Hash,
+	/// SCALE encoded header.
+	pub header: Bytes,
+	/// Extrinsics of the block, in order.
+	pub extrinsics: Vec<ExportedExtrinsic>,
+	/// Events of the block, `None` if its state is not available.
+	pub events: Option<Vec<DecodedEvent>>,
+	/// Justifications of the block.
+	pub justifications: Vec<ExportedJustification>,
+}
+
+/// An extrinsic of a [`DecodedBlock`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedExtrinsic {
+	/// Index of the extrinsic in its block.
+	pub index: u32,
+	/// Hash of the extrinsic.
+	pub hash: Hash,
+	/// SCALE encoded extrinsic, as found in the block.
+	pub raw: Bytes,
+	/// Signer, nonce, tip and call, `None` if the extrinsic could not be decoded.
+	#[serde(flatten)]
+	pub extrinsic: Option<DecodedExtrinsic>,
+	/// Whether the dispatch succeeded, `None` if the events of the block are not available.
+	pub success: Option<bool>,
+	/// Why the dispatch failed.
+	pub error: Option<DecodedError>,
+}
+
+/// A justification of a [`DecodedBlock`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedJustification {
+	/// Id of the consensus engine, e.g. `FRNK` for GRANDPA.
+	pub engine: String,
+	/// SCALE encoded justification.
+	pub data: Bytes,
+}
+
+impl ExportBlocksCmd {
+	/// Run the export-blocks command
+	pub async fn run(
+		&self,
+		client: Arc<FullClient>,
+		database_config: DatabaseConfig,
+	) -> Result<()> {
+		if !self.decoded {
+			return self.base.run(client, database_config).await
+		}
+
+		let from = self.base.from.as_ref().map(|from| from.parse()).transpose()?.unwrap_or(1);
+		let to = match &self.base.to {
+			Some(to) => to.parse()?,
+			None => client.info().best_number,
+		};
+		let mut output: Box<dyn Write> = match &self.base.output {
+			Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+			None => Box::new(BufWriter::new(io::stdout())),
+		};
+
+		let mut metadata = MetadataCache::default();
+		for number in from..=to {
+			let block = export_block(&client, &mut metadata, number)?;
+			serde_json::to_writer(&mut output, &block).map_err(|e| e.to_string())?;
+			writeln!(output)?;
+		}
+		output.flush()?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportBlocksCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.base.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.base.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.base.database_params)
+	}
+}
+
+fn export_block(
+	client: &FullClient,
+	metadata: &mut MetadataCache,
+	number: BlockNumber,
+) -> Result<DecodedBlock> {
+	let block = client
+		.block(&BlockId::Number(number))?
+		.ok_or_else(|| format!("Block #{} not found", number))?;
+	let header = &block.block.header;
+	let hash = header.hash();
+
+	// The block was built by the runtime of its parent.
+	let parent = if number == 0 { hash } else { *header.parent_hash() };
+	let at = if metadata.get(client, parent).is_ok() {
+		parent
+	} else {
+		log::warn!("Decoding block #{} with the metadata of the best block", number);
+		client.info().best_hash
+	};
+	let decoder = Decoder::new(metadata.get(client, at)?);
+
+	let events = StorageProvider::<Block, FullBackend>::storage(
+		client,
+		&BlockId::Hash(hash),
+		&StorageKey(events_key()),
+	)
+	.ok()
+	.and_then(|events| {
+		let events = events.map(|events| events.0).unwrap_or_default();
+		decoder
+			.events(&mut &events[..])
+			.map_err(|e| log::warn!("Unable to decode the events of block #{}: {}", number, e))
+			.ok()
+	})
+	.map(|events| events.into_iter().map(|(event, _)| event).collect::<Vec<_>>());
+
+	let mut extrinsics = Vec::new();
+	for (index, xt) in block.block.extrinsics.iter().enumerate() {
+		let raw = xt.encode();
+		let extrinsic = decoder
+			.extrinsic(&mut &raw[..], &mut Vec::new())
+			.map_err(|e| {
+				log::warn!("Unable to decode extrinsic #{} of block #{}: {}", index, number, e)
+			})
+			.ok();
+		let failed = events.as_ref().map(|events| {
+			events.iter().find(|event| {
+				event.extrinsic == Some(index as u32) &&
+					event.pallet == "System" &&
+					event.name == "ExtrinsicFailed"
+			})
+		});
+		extrinsics.push(ExportedExtrinsic {
+			index: index as u32,
+			hash: Hash::from(blake2_256(&raw)),
+			raw: Bytes(raw),
+			extrinsic,
+			success: failed.map(|failed| failed.is_none()),
+			error: failed.flatten().and_then(|event| event.error.clone()),
+		});
+	}
+
+	let justifications = block
+		.justifications
+		.iter()
+		.flat_map(|justifications| justifications.iter())
+		.map(|(engine, data)| ExportedJustification {
+			engine: String::from_utf8_lossy(engine).into_owned(),
+			data: Bytes(data.clone()),
+		})
+		.collect();
+
+	Ok(DecodedBlock {
+		number,
+		hash,
+		parent_hash: *header.parent_hash(),
+		header: Bytes(header.encode()),
+		extrinsics,
+		events,
+		justifications,
+	})
+}
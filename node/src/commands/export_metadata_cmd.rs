@@ -0,0 +1,89 @@
+use crate::metadata;
+use codec::Decode;
+use frame_metadata::RuntimeMetadataPrefixed;
+use node_template_runtime::{Balance, BlockNumber, Hash, Index, WASM_BINARY};
+use sc_cli::Result;
+use serde_json::json;
+use std::{fs, mem::size_of, path::PathBuf};
+use structopt::StructOpt;
+
+/// The `export-metadata` command used to write the metadata of a runtime to files, for clients
+/// generating their bindings without a running chain.
+///
+/// The metadata is read by executing `Metadata_metadata` of the Wasm runtime built into this node,
+/// or of `--wasm`, exactly as a node would return it from `state_getMetadata`. Three files are
+/// written to `--output-dir`:
+///
+/// - `metadata.scale`, the SCALE encoded metadata,
+/// - `metadata.json`, the runtime version and the metadata as JSON,
+/// - `types.ts`, the definitions of the custom types of the runtime for `@polkadot/api`.
+#[derive(Debug, StructOpt)]
+pub struct ExportMetadataCmd {
+	/// Wasm runtime, compressed or not, to read the metadata of instead of the built-in one.
+	#[structopt(long, parse(from_os_str))]
+	pub wasm: Option<PathBuf>,
+
+	/// Directory to write the files to, created if missing.
+	#[structopt(short, long, parse(from_os_str), default_value = ".")]
+	pub output_dir: PathBuf,
+}
+
+impl ExportMetadataCmd {
+	/// Run the export-metadata command
+	pub fn run(&self) -> Result<()> {
+		let code = match &self.wasm {
+			Some(path) => fs::read(path)?,
+			None => WASM_BINARY
+				.ok_or(
+					"The Wasm runtime was not built, pass `--wasm` or build without \
+					`SKIP_WASM_BUILD`",
+				)?
+				.to_vec(),
+		};
+		let (version, encoded) = metadata::from_wasm(&code)?;
+		let prefixed = RuntimeMetadataPrefixed::decode(&mut &encoded[..])
+			.map_err(|e| format!("Invalid metadata: {:?}", e))?;
+
+		fs::create_dir_all(&self.output_dir)?;
+		fs::write(self.output_dir.join("metadata.scale"), &encoded)?;
+		let json =
+			serde_json::to_string_pretty(&json!({ "version": version, "metadata": prefixed }))
+				.map_err(|e| format!("Unable to encode the metadata as JSON: {}", e))?;
+		fs::write(self.output_dir.join("metadata.json"), json)?;
+
+		let mut types = format!(
+			"// Custom types of `{}` version {}, written by `node-template export-metadata`.\n\
+			 export default {{\n  types: {{\n",
+			version.spec_name, version.spec_version
+		);
+		for (name, definition) in custom_types() {
+			types.push_str(&format!("    {}: '{}',\n", name, definition));
+		}
+		types.push_str("  },\n};\n");
+		fs::write(self.output_dir.join("types.ts"), types)?;
+
+		println!(
+			"Wrote the metadata of `{}` version {} to {}",
+			version.spec_name,
+			version.spec_version,
+			self.output_dir.display()
+		);
+		Ok(())
+	}
+}
+
+/// Definitions of the types the metadata names after the aliases of the runtime, by name.
+///
+/// Integers take the size of the runtime's alias, so that changing one shows up in the file.
+fn custom_types() -> Vec<(&'static str, String)> {
+	let bits = |size: usize| format!("u{}", size * 8);
+	vec![
+		("AccountId", "AccountId32".into()),
+		("Address", "MultiAddress".into()),
+		("LookupSource", "MultiAddress".into()),
+		("Balance", bits(size_of::<Balance>())),
+		("BlockNumber", bits(size_of::<BlockNumber>())),
+		("Index", bits(size_of::<Index>())),
+		("Hash", format!("H{}", size_of::<Hash>() * 8)),
+	]
+}
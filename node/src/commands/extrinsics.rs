@@ -0,0 +1,88 @@
+//! Signed extrinsics built with the types of the runtime, for the subcommands generating load.
+
+use codec::Encode;
+use node_template_runtime::{
+	pallet_template, Address, BalancesCall, Call, Hash, Index, Runtime, SignedExtra,
+	UncheckedExtrinsic,
+};
+use sp_core::{sr25519, Pair};
+use sp_runtime::{
+	generic::{Era, SignedPayload},
+	AccountId32,
+};
+use sp_version::RuntimeVersion;
+use std::str::FromStr;
+
+/// Amount of each transfer, above the existential deposit so that a transfer to a new account
+/// creates it.
+pub const TRANSFER_AMOUNT: u128 = 1_000_000_000;
+
+/// Kind of extrinsic to generate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filler {
+	/// `Balances::transfer`.
+	Transfer,
+	/// `TemplateModule::do_something`.
+	DoSomething,
+}
+
+impl FromStr for Filler {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, String> {
+		match s {
+			"transfer" => Ok(Filler::Transfer),
+			"do-something" => Ok(Filler::DoSomething),
+			_ => Err(format!("Unknown extrinsic `{}`, expected `transfer` or `do-something`", s)),
+		}
+	}
+}
+
+impl Filler {
+	/// Name of the call, e.g. `Balances::transfer`.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Filler::Transfer => "Balances::transfer",
+			Filler::DoSomething => "TemplateModule::do_something",
+		}
+	}
+
+	/// The `index`-th call of this kind, transfers going to `dest`.
+	pub fn call(&self, index: u32, dest: AccountId32) -> Call {
+		match self {
+			Filler::Transfer =>
+				Call::Balances(BalancesCall::transfer(Address::Id(dest), TRANSFER_AMOUNT)),
+			Filler::DoSomething => Call::TemplateModule(pallet_template::Call::do_something(index)),
+		}
+	}
+}
+
+/// Build an immortal extrinsic signed by `signer` for the runtime `version`, without tip.
+pub fn signed(
+	signer: &sr25519::Pair,
+	nonce: Index,
+	genesis_hash: Hash,
+	version: &RuntimeVersion,
+	call: Call,
+) -> UncheckedExtrinsic {
+	let extra: SignedExtra = (
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+	);
+	let additional =
+		(version.spec_version, version.transaction_version, genesis_hash, genesis_hash, (), (), ());
+	let payload = SignedPayload::from_raw(call, extra, additional);
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+	UncheckedExtrinsic::new_signed(
+		call,
+		Address::Id(signer.public().into()),
+		signature.into(),
+		extra,
+	)
+}
@@ -0,0 +1,170 @@
+use crate::chain_spec;
+use codec::{Decode, Encode};
+use node_template_runtime::{Balance, Index, WASM_BINARY};
+use sc_cli::{CliConfiguration, Result, SharedParams};
+use sc_service::ChainSpec;
+use sp_core::{
+	hashing::{blake2_128, twox_128, twox_64},
+	sr25519,
+	storage::well_known_keys,
+};
+use sp_finality_grandpa::{
+	AuthorityId as GrandpaId, VersionedAuthorityList, GRANDPA_AUTHORITIES_KEY,
+};
+use std::{collections::BTreeMap, fmt::Debug, io::Write, path::PathBuf};
+use structopt::StructOpt;
+
+type AccountInfo = frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+
+/// Balance given to the dev account so that it can pay for the transactions it sends as `Sudo`.
+const DEV_ENDOWMENT: Balance = 1 << 60;
+
+/// The `fork-off` command used to turn exported chain state into a bootable development chain.
+///
+/// The chain spec given with `--chain` is usually the output of `export-state`. Its state is kept
+/// as is, except that:
+///
+/// - the Aura and GRANDPA authorities are replaced by `Alice`,
+/// - `Alice` becomes the `Sudo` key and is endowed with some funds,
+/// - the runtime code is replaced by the `WASM_BINARY` of this node,
+/// - the `System` block data is reset so that the fork starts again from block 0.
+#[derive(Debug, StructOpt)]
+pub struct ForkOffCmd {
+	/// Path of the chain spec to write. Defaults to stdout.
+	#[structopt(long, short = "o", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ForkOffCmd {
+	/// Run the fork-off command
+	pub fn run(&self, spec: Box<dyn ChainSpec>) -> Result<()> {
+		let wasm_binary =
+			WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
+
+		let mut storage = spec.as_storage_builder().build_storage()?;
+		let top = &mut storage.top;
+
+		top.insert(well_known_keys::CODE.to_vec(), wasm_binary.to_vec());
+		reset_block_data(top);
+		replace_authorities(top);
+		replace_sudo_key(top)?;
+
+		let mut fork = chain_spec::development_config()?;
+		fork.set_storage(storage);
+		let json = sc_service::chain_ops::build_spec(&fork, true)?;
+
+		match &self.output {
+			Some(path) => std::fs::write(path, json)?,
+			None =>
+				if std::io::stdout().write_all(json.as_bytes()).is_err() {
+					let _ = std::io::stderr().write_all(b"Error writing to stdout\n");
+				},
+		}
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ForkOffCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// The raw storage of a chain spec.
+type Top = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Key of a storage value, or prefix of all the entries of a storage map.
+fn storage_prefix(pallet: &str, item: &str) -> Vec<u8> {
+	[twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat()
+}
+
+fn remove_prefix(top: &mut Top, prefix: &[u8]) {
+	top.retain(|key, _| !key.starts_with(prefix));
+}
+
+/// Make the state look like a genesis state again, as `frame_system` builds it.
+fn reset_block_data(top: &mut Top) {
+	// The hash `frame_system` uses as a placeholder for the parent of the genesis block.
+	let hash69 = [69u8; 32];
+
+	for item in &[
+		"Number",
+		"ParentHash",
+		"BlockHash",
+		"Digest",
+		"Events",
+		"EventCount",
+		"EventTopics",
+		"ExtrinsicCount",
+		"ExtrinsicData",
+		"BlockWeight",
+		"AllExtrinsicsLen",
+		"ExecutionPhase",
+	] {
+		remove_prefix(top, &storage_prefix("System", item));
+	}
+
+	let block_number: node_template_runtime::BlockNumber = 0;
+	let block_hash_key = [
+		storage_prefix("System", "BlockHash"),
+		twox_64(&block_number.encode()).to_vec(),
+		block_number.encode(),
+	]
+	.concat();
+	top.insert(block_hash_key, hash69.to_vec());
+	top.insert(storage_prefix("System", "ParentHash"), hash69.to_vec());
+	top.insert(well_known_keys::EXTRINSIC_INDEX.to_vec(), 0u32.encode());
+
+	// Slots of the fork start over from the current time.
+	top.remove(&storage_prefix("Aura", "CurrentSlot"));
+}
+
+/// Hand block production and finality to `Alice`, as on the `dev` chain.
+fn replace_authorities(top: &mut Top) {
+	let (aura, grandpa) = chain_spec::authority_keys_from_seed("Alice");
+
+	top.insert(storage_prefix("Aura", "Authorities"), vec![aura].encode());
+
+	let authorities: Vec<(GrandpaId, u64)> = vec![(grandpa, 1)];
+	top.insert(
+		GRANDPA_AUTHORITIES_KEY.to_vec(),
+		VersionedAuthorityList::from(authorities).encode(),
+	);
+	// Forget about any authority set change that was in flight on the original chain.
+	for item in &["State", "PendingChange", "NextForced", "Stalled", "CurrentSetId", "SetIdSession"]
+	{
+		remove_prefix(top, &storage_prefix("Grandpa", item));
+	}
+}
+
+/// Make `Alice` the `Sudo` key and make sure she can pay for her transactions.
+fn replace_sudo_key(top: &mut Top) -> Result<()> {
+	let alice = chain_spec::get_account_id_from_seed::<sr25519::Public>("Alice");
+	top.insert(storage_prefix("Sudo", "Key"), alice.encode());
+
+	let account_key =
+		[storage_prefix("System", "Account"), blake2_128(&alice.encode()).to_vec(), alice.encode()]
+			.concat();
+	let mut account = match top.get(&account_key) {
+		Some(raw) => AccountInfo::decode(&mut &raw[..])
+			.map_err(|e| format!("Invalid account info for Alice: {:?}", e))?,
+		None => AccountInfo { providers: 1, ..Default::default() },
+	};
+	account.data.free = account.data.free.saturating_add(DEV_ENDOWMENT);
+	top.insert(account_key, account.encode());
+
+	let issuance_key = storage_prefix("Balances", "TotalIssuance");
+	let issuance = match top.get(&issuance_key) {
+		Some(raw) => Balance::decode(&mut &raw[..])
+			.map_err(|e| format!("Invalid total issuance: {:?}", e))?,
+		None => 0,
+	};
+	top.insert(issuance_key, issuance.saturating_add(DEV_ENDOWMENT).encode());
+
+	Ok(())
+}
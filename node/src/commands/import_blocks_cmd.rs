@@ -0,0 +1,157 @@
+use super::export_blocks_cmd::DecodedBlock;
+use crate::service::FullClient;
+use codec::Decode;
+use node_template_runtime::opaque::{Block, Header, UncheckedExtrinsic};
+use sc_cli::{CliConfiguration, Error, ImportParams, Result, SharedParams};
+use sc_consensus::DefaultImportQueue;
+use sc_service::chain_ops::import_blocks;
+use sp_runtime::{
+	generic::SignedBlock,
+	traits::{Block as BlockT, Header as _},
+	ConsensusEngineId, Justifications,
+};
+use std::{
+	convert::TryInto,
+	fs::File,
+	io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom},
+	sync::Arc,
+};
+use structopt::StructOpt;
+
+/// The `import-blocks` command of `sc-cli`, with a mode importing decoded blocks.
+///
+/// With `--decoded`, the input is the JSON lines written by `export-blocks --decoded`. Blocks are
+/// rebuilt from their SCALE encoded header and extrinsics, the decoded fields are only checked to
+/// be valid JSON. Lines are read as the blocks are imported, the input is never held in memory.
+#[derive(Debug, StructOpt)]
+pub struct ImportBlocksCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub base: sc_cli::ImportBlocksCmd,
+
+	/// Import the JSON lines written by `export-blocks --decoded`.
+	#[structopt(long, conflicts_with = "binary")]
+	pub decoded: bool,
+}
+
+impl ImportBlocksCmd {
+	/// Run the import-blocks command
+	pub async fn run(
+		&self,
+		client: Arc<FullClient>,
+		import_queue: DefaultImportQueue<Block, FullClient>,
+	) -> Result<()> {
+		if !self.decoded {
+			return self.base.run(client, import_queue).await
+		}
+
+		let input: Box<dyn BufRead + Send> = match &self.base.input {
+			Some(path) => Box::new(BufReader::new(File::open(path)?)),
+			None => Box::new(BufReader::new(io::stdin())),
+		};
+		// Feed the blocks to the importer of `sc-service` in its JSON format, which, unlike the
+		// binary one, does not start with the number of blocks.
+		let blocks =
+			Blocks { lines: input.lines(), line: 0, block: Cursor::new(Vec::new()), read: 0 };
+		import_blocks(client, import_queue, blocks, false, false)
+			.await
+			.map_err(Into::into)
+	}
+}
+
+impl CliConfiguration for ImportBlocksCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.base.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.base.import_params)
+	}
+}
+
+/// Reader of the JSON lines of `export-blocks --decoded`, as the JSON blocks `import_blocks` reads.
+///
+/// A block is only rebuilt once the previous one has been read.
+struct Blocks {
+	lines: io::Lines<Box<dyn BufRead + Send>>,
+	/// Number of lines read.
+	line: usize,
+	/// The rebuilt block being read.
+	block: Cursor<Vec<u8>>,
+	/// Number of bytes read.
+	read: u64,
+}
+
+impl Read for Blocks {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		loop {
+			let read = self.block.read(buf)?;
+			if read > 0 || buf.is_empty() {
+				self.read += read as u64;
+				return Ok(read)
+			}
+
+			let json = match self.lines.next() {
+				Some(json) => json?,
+				None => return Ok(0),
+			};
+			self.line += 1;
+			if json.trim().is_empty() {
+				continue
+			}
+			let block = serde_json::from_str(&json)
+				.map_err(|e| format!("Invalid block on line {}: {}", self.line, e))
+				.map_err(Error::Input)
+				.and_then(signed_block)
+				.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+			let mut encoded = serde_json::to_vec(&block)?;
+			encoded.push(b'\n');
+			self.block = Cursor::new(encoded);
+		}
+	}
+}
+
+impl Seek for Blocks {
+	fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+		match pos {
+			SeekFrom::Current(0) => Ok(self.read),
+			_ => Err(io::Error::new(io::ErrorKind::Other, "Decoded blocks are read sequentially")),
+		}
+	}
+}
+
+/// Rebuild a block from the SCALE encoded parts of its export.
+fn signed_block(block: DecodedBlock) -> Result<SignedBlock<Block>> {
+	let header = Header::decode(&mut &block.header[..])
+		.map_err(|e| format!("Invalid header of block #{}: {}", block.number, e))?;
+	if header.hash() != block.hash {
+		return Err(format!("Header of block #{} does not match its hash", block.number).into())
+	}
+	let extrinsics = block
+		.extrinsics
+		.iter()
+		.map(|xt| {
+			UncheckedExtrinsic::decode(&mut &xt.raw[..]).map_err(|e| {
+				format!("Invalid extrinsic #{} of block #{}: {}", xt.index, block.number, e)
+			})
+		})
+		.collect::<std::result::Result<Vec<_>, _>>()?;
+
+	let mut justifications: Option<Justifications> = None;
+	for justification in block.justifications {
+		let engine: ConsensusEngineId = justification
+			.engine
+			.as_bytes()
+			.try_into()
+			.map_err(|_| format!("Invalid consensus engine `{}`", justification.engine))?;
+		let justification = (engine, justification.data.0);
+		match &mut justifications {
+			Some(justifications) => {
+				justifications.append(justification);
+			},
+			None => justifications = Some(justification.into()),
+		}
+	}
+
+	Ok(SignedBlock { block: Block::new(header, extrinsics), justifications })
+}
@@ -0,0 +1,412 @@
+use super::{
+	extrinsics::{signed, Filler},
+	remote::Remote,
+};
+use codec::{Decode, Encode};
+use jsonrpsee_ws_client::types::{traits::SubscriptionClient, v2::params::JsonRpcParams};
+use node_template_runtime::{
+	Address, Balance, BalancesCall, Call, Hash, Index, UncheckedExtrinsic,
+};
+use sc_cli::Result;
+use sc_transaction_pool_api::TransactionStatus;
+use serde::Serialize;
+use serde_json::json;
+use sp_core::{
+	crypto::Ss58Codec,
+	hashing::{blake2_128, twox_128},
+	sr25519, Bytes, Pair,
+};
+use sp_runtime::AccountId32;
+use sp_version::RuntimeVersion;
+use std::{
+	collections::BTreeMap,
+	fs,
+	path::PathBuf,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use structopt::StructOpt;
+
+/// The `load` command used to submit signed extrinsics to running nodes at a steady rate.
+///
+/// The extrinsics are signed by `--accounts` accounts derived from `--seed` as `<SEED>//<INDEX>`,
+/// which are funded from the seed account first when they hold less than half of `--fund`. The
+/// accounts sign in turn, each always submitting to the same node so that its extrinsics reach the
+/// pool in the order of their nonces. Nonces are tracked locally; an account whose extrinsic is
+/// rejected by the pool fetches its next nonce from the node again. Transfers go from every account
+/// to the next one, so that the load does not grow the state.
+///
+/// Every extrinsic is watched until it is in a block, or dropped from the pool. The inclusion
+/// latency, the rejections by reason and the achieved throughput are then printed, and written as
+/// JSON to `--output`.
+///
+/// Extrinsics are built with the types of this node's runtime: the nodes must run the same runtime.
+#[derive(Debug, StructOpt)]
+pub struct LoadCmd {
+	/// WebSocket RPC endpoint of a node to submit to. May be given several times.
+	#[structopt(long = "url", value_name = "URL", default_value = "ws://127.0.0.1:9944")]
+	pub urls: Vec<String>,
+
+	/// Secret URI of the funded sr25519 key the accounts are derived from.
+	#[structopt(long, default_value = "//Alice")]
+	pub seed: String,
+
+	/// Number of accounts signing the extrinsics.
+	#[structopt(long, default_value = "100")]
+	pub accounts: u32,
+
+	/// Balance each account is funded with.
+	#[structopt(long, default_value = "1000000000000000")]
+	pub fund: Balance,
+
+	/// Extrinsic to submit, `transfer` or `do-something`.
+	#[structopt(long, default_value = "transfer")]
+	pub extrinsic: Filler,
+
+	/// Extrinsics to submit per second, over all nodes.
+	#[structopt(long, default_value = "10")]
+	pub tps: f64,
+
+	/// Seconds to submit for.
+	#[structopt(long, default_value = "60")]
+	pub duration: u64,
+
+	/// Seconds to wait for the inclusion of the extrinsics once all are submitted.
+	#[structopt(long, default_value = "60")]
+	pub wait: u64,
+
+	/// Write the report as JSON to this file.
+	#[structopt(short, long, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+}
+
+/// What became of a submitted extrinsic.
+enum Outcome {
+	/// In a block, `latency` after its submission.
+	Included { latency: Duration, at: Instant },
+	/// Rejected by the pool when submitted.
+	Rejected(String),
+	/// Accepted by the pool, then removed from it without being included.
+	Dropped(String),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+	extrinsic: &'static str,
+	accounts: u32,
+	target_tps: f64,
+	submitted: usize,
+	/// Rate of the submissions, below the target if the nodes could not keep up.
+	submitted_tps: f64,
+	included: usize,
+	/// Rate of the inclusions, from the first submission to the last inclusion.
+	achieved_tps: f64,
+	/// Number of extrinsics rejected by the pool, by reason.
+	rejected: BTreeMap<String, usize>,
+	/// Number of extrinsics dropped from the pool, by status.
+	dropped: BTreeMap<String, usize>,
+	/// Number of extrinsics still neither included nor dropped at the end of `--wait`.
+	pending: usize,
+	latency_ms: Option<Latency>,
+	nodes: Vec<NodeReport>,
+}
+
+/// Percentiles of the inclusion latency, in milliseconds.
+#[derive(Serialize)]
+struct Latency {
+	min: f64,
+	p50: f64,
+	p90: f64,
+	p99: f64,
+	max: f64,
+}
+
+#[derive(Serialize)]
+struct NodeReport {
+	url: String,
+	submitted: usize,
+	included: usize,
+}
+
+impl LoadCmd {
+	/// Run the load command
+	pub fn run(&self) -> Result<()> {
+		if self.accounts == 0 {
+			return Err("`--accounts` must be positive".into())
+		}
+		let period = self.period()?;
+		tokio::runtime::Runtime::new()?.block_on(self.load(period))
+	}
+
+	/// Time between two submissions. `tokio` panics on a zero period, which a `--tps` above a
+	/// billion rounds to, and `Duration` on an infinite one.
+	fn period(&self) -> Result<Duration> {
+		let secs = 1.0 / self.tps;
+		if !self.tps.is_finite() || self.tps <= 0.0 || secs > u64::MAX as f64 {
+			return Err(format!("`--tps` must be positive and finite, not {}", self.tps).into())
+		}
+		let period = Duration::from_secs_f64(secs);
+		if period == Duration::from_secs(0) {
+			return Err(format!("`--tps` must be at most 1e9, not {}", self.tps).into())
+		}
+		Ok(period)
+	}
+
+	async fn load(&self, period: Duration) -> Result<()> {
+		let mut remotes = Vec::new();
+		for url in &self.urls {
+			remotes.push(Arc::new(Remote::connect(url).await?));
+		}
+		let genesis_hash = remotes[0].block_hash(0).await?;
+		let version = remotes[0].runtime_version(None).await?;
+		let seed = pair(&self.seed)?;
+		let accounts = (0..self.accounts)
+			.map(|index| pair(&format!("{}//{}", self.seed, index)))
+			.collect::<Result<Vec<_>>>()?;
+		self.fund(&remotes[0], &seed, &accounts, genesis_hash, &version).await?;
+
+		let total = (self.tps * self.duration as f64).round() as usize;
+		println!(
+			"Submitting {} {} from {} accounts at {} tps to {} node(s)",
+			total,
+			self.extrinsic.name(),
+			self.accounts,
+			self.tps,
+			remotes.len(),
+		);
+		let (rejections, mut rejected_accounts) = futures::channel::mpsc::unbounded();
+		let mut nonces = vec![None; accounts.len()];
+		let mut submissions = Vec::with_capacity(total);
+		let mut interval = tokio::time::interval(period);
+		let start = Instant::now();
+		for index in 0..total {
+			interval.tick().await;
+			while let Ok(Some(account)) = rejected_accounts.try_next() {
+				nonces[account] = None;
+			}
+
+			let account = index % accounts.len();
+			let node = account % remotes.len();
+			let remote = remotes[node].clone();
+			let nonce = match nonces[account] {
+				Some(nonce) => nonce,
+				None => next_nonce(&remote, &accounts[account]).await?,
+			};
+			nonces[account] = Some(nonce + 1);
+			let dest = accounts[(account + 1) % accounts.len()].public().into();
+			let call = self.extrinsic.call(index as u32, dest);
+			let xt = signed(&accounts[account], nonce, genesis_hash, &version, call);
+			let rejections = rejections.clone();
+			let submission = tokio::spawn(async move {
+				let outcome = submit(&remote, xt).await;
+				if let Outcome::Rejected(_) = outcome {
+					let _ = rejections.unbounded_send(account);
+				}
+				outcome
+			});
+			submissions.push((node, submission));
+		}
+		let submitted_in = start.elapsed();
+
+		let mut report = Report {
+			extrinsic: self.extrinsic.name(),
+			accounts: self.accounts,
+			target_tps: self.tps,
+			submitted: total,
+			submitted_tps: total as f64 / submitted_in.as_secs_f64(),
+			included: 0,
+			achieved_tps: 0.0,
+			rejected: BTreeMap::new(),
+			dropped: BTreeMap::new(),
+			pending: 0,
+			latency_ms: None,
+			nodes: self
+				.urls
+				.iter()
+				.map(|url| NodeReport { url: url.clone(), submitted: 0, included: 0 })
+				.collect(),
+		};
+		let deadline = tokio::time::Instant::now() + Duration::from_secs(self.wait);
+		let mut latencies = Vec::new();
+		let mut last_inclusion = start;
+		for (node, mut submission) in submissions {
+			report.nodes[node].submitted += 1;
+			let outcome = match tokio::time::timeout_at(deadline, &mut submission).await {
+				Ok(outcome) => outcome.map_err(|e| format!("Submission failed: {}", e))?,
+				Err(_) => {
+					submission.abort();
+					report.pending += 1;
+					continue
+				},
+			};
+			match outcome {
+				Outcome::Included { latency, at } => {
+					report.nodes[node].included += 1;
+					latencies.push(latency);
+					last_inclusion = last_inclusion.max(at);
+				},
+				Outcome::Rejected(reason) => *report.rejected.entry(reason).or_default() += 1,
+				Outcome::Dropped(status) => *report.dropped.entry(status).or_default() += 1,
+			}
+		}
+		report.included = latencies.len();
+		if !latencies.is_empty() {
+			report.achieved_tps = latencies.len() as f64 / (last_inclusion - start).as_secs_f64();
+			latencies.sort();
+			let percentile = |p: f64| {
+				let index = ((latencies.len() - 1) as f64 * p).round() as usize;
+				latencies[index].as_secs_f64() * 1000.0
+			};
+			report.latency_ms = Some(Latency {
+				min: percentile(0.0),
+				p50: percentile(0.5),
+				p90: percentile(0.9),
+				p99: percentile(0.99),
+				max: percentile(1.0),
+			});
+		}
+
+		print(&report, submitted_in);
+		if let Some(output) = &self.output {
+			let json = serde_json::to_string_pretty(&report)
+				.map_err(|e| format!("Unable to encode the report: {}", e))?;
+			fs::write(output, json)?;
+		}
+		Ok(())
+	}
+
+	/// Transfer `--fund` from `seed` to the accounts holding less than half of it, and wait until
+	/// all transfers are in a block.
+	async fn fund(
+		&self,
+		remote: &Remote,
+		seed: &sr25519::Pair,
+		accounts: &[sr25519::Pair],
+		genesis_hash: Hash,
+		version: &RuntimeVersion,
+	) -> Result<()> {
+		let mut transfers = Vec::new();
+		let mut nonce = next_nonce(remote, seed).await?;
+		for account in accounts {
+			let id = AccountId32::from(account.public());
+			if free_balance(remote, &id).await? >= self.fund / 2 {
+				continue
+			}
+			let call = Call::Balances(BalancesCall::transfer(Address::Id(id), self.fund));
+			transfers.push(submit(remote, signed(seed, nonce, genesis_hash, version, call)));
+			nonce += 1;
+		}
+		if transfers.is_empty() {
+			return Ok(())
+		}
+
+		println!("Funding {} accounts", transfers.len());
+		let wait = Duration::from_secs(self.wait);
+		let outcomes = tokio::time::timeout(wait, futures::future::join_all(transfers))
+			.await
+			.map_err(|_| "Funding transfers not included in time")?;
+		for outcome in outcomes {
+			match outcome {
+				Outcome::Included { .. } => (),
+				Outcome::Rejected(reason) =>
+					return Err(format!("Funding transfer rejected: {}", reason).into()),
+				Outcome::Dropped(status) =>
+					return Err(format!("Funding transfer not included: {}", status).into()),
+			}
+		}
+		Ok(())
+	}
+}
+
+fn pair(suri: &str) -> Result<sr25519::Pair> {
+	sr25519::Pair::from_string(suri, None)
+		.map_err(|e| format!("Invalid secret URI: {:?}", e).into())
+}
+
+async fn next_nonce(remote: &Remote, account: &sr25519::Pair) -> Result<Index> {
+	let account = account.public().to_ss58check();
+	remote.request("system_accountNextIndex", vec![json!(account)]).await
+}
+
+async fn free_balance(remote: &Remote, account: &AccountId32) -> Result<Balance> {
+	let encoded = account.encode();
+	let key =
+		[&twox_128(b"System")[..], &twox_128(b"Account"), &blake2_128(&encoded), &encoded].concat();
+	let info = match remote.storage(&key, None).await? {
+		Some(info) => info,
+		None => return Ok(0),
+	};
+	let info = frame_system::AccountInfo::<Index, pallet_balances::AccountData<Balance>>::decode(
+		&mut &info[..],
+	)
+	.map_err(|e| format!("Invalid account {}: {}", account, e))?;
+	Ok(info.data.free)
+}
+
+/// Submit `xt` to `remote` and watch it until it is in a block or dropped.
+async fn submit(remote: &Remote, xt: UncheckedExtrinsic) -> Outcome {
+	let start = Instant::now();
+	let mut statuses = match remote
+		.client()
+		.subscribe::<TransactionStatus<Hash, Hash>>(
+			"author_submitAndWatchExtrinsic",
+			JsonRpcParams::Array(vec![json!(Bytes(xt.encode()))]),
+			"author_unwatchExtrinsic",
+		)
+		.await
+	{
+		Ok(statuses) => statuses,
+		Err(e) => return Outcome::Rejected(e.to_string()),
+	};
+
+	loop {
+		let status = match statuses.next().await {
+			Ok(Some(status)) => status,
+			Ok(None) => return Outcome::Dropped("Unwatched".into()),
+			Err(e) => return Outcome::Dropped(e.to_string()),
+		};
+		let dropped = match status {
+			TransactionStatus::InBlock(_) | TransactionStatus::Finalized(_) =>
+				return Outcome::Included { latency: start.elapsed(), at: Instant::now() },
+			TransactionStatus::Future |
+			TransactionStatus::Ready |
+			TransactionStatus::Broadcast(_) |
+			TransactionStatus::Retracted(_) => continue,
+			TransactionStatus::Usurped(_) => "Usurped",
+			TransactionStatus::FinalityTimeout(_) => "FinalityTimeout",
+			TransactionStatus::Dropped => "Dropped",
+			TransactionStatus::Invalid => "Invalid",
+		};
+		return Outcome::Dropped(dropped.into())
+	}
+}
+
+fn print(report: &Report, submitted_in: Duration) {
+	println!(
+		"Submitted {} in {:.1} s ({:.1} tps), {} included ({:.1} tps)",
+		report.submitted,
+		submitted_in.as_secs_f64(),
+		report.submitted_tps,
+		report.included,
+		report.achieved_tps,
+	);
+	if let Some(latency) = &report.latency_ms {
+		println!(
+			"Inclusion latency: min {:.0} ms, p50 {:.0} ms, p90 {:.0} ms, p99 {:.0} ms, max {:.0} ms",
+			latency.min, latency.p50, latency.p90, latency.p99, latency.max,
+		);
+	}
+	for (reason, count) in &report.rejected {
+		println!("Rejected by the pool: {} x {}", count, reason);
+	}
+	for (status, count) in &report.dropped {
+		println!("Dropped from the pool: {} x {}", count, status);
+	}
+	if report.pending > 0 {
+		println!("Not included after the wait: {}", report.pending);
+	}
+	for node in &report.nodes {
+		println!("{}: {} submitted, {} included", node.url, node.submitted, node.included);
+	}
+}
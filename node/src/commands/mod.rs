@@ -0,0 +1,30 @@
+//! Node-specific subcommands that are not provided by `sc-cli`.
+
+mod benchmark_block_cmd;
+mod check_metadata_cmd;
+mod create_snapshot_cmd;
+mod export_blocks_cmd;
+mod export_metadata_cmd;
+mod extrinsics;
+mod fork_off_cmd;
+mod import_blocks_cmd;
+mod load_cmd;
+mod query_cmd;
+mod remote;
+mod replay_block_cmd;
+mod state_diff_cmd;
+mod storage;
+mod tx_cmd;
+
+pub use benchmark_block_cmd::BenchmarkBlockCmd;
+pub use check_metadata_cmd::CheckMetadataCmd;
+pub use create_snapshot_cmd::CreateSnapshotCmd;
+pub use export_blocks_cmd::ExportBlocksCmd;
+pub use export_metadata_cmd::ExportMetadataCmd;
+pub use fork_off_cmd::ForkOffCmd;
+pub use import_blocks_cmd::ImportBlocksCmd;
+pub use load_cmd::LoadCmd;
+pub use query_cmd::QueryCmd;
+pub use replay_block_cmd::ReplayBlockCmd;
+pub use state_diff_cmd::StateDiffCmd;
+pub use tx_cmd::TxCmd;
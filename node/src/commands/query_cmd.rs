@@ -0,0 +1,215 @@
+use super::{
+	remote::Remote,
+	storage::{decode_keys, decode_value, hash, parse_hash},
+};
+use crate::{
+	encoder::{parse_arg, Encoder},
+	metadata::Metadata,
+	service::{FullBackend, FullClient},
+};
+use node_template_runtime::{opaque::Block, Hash};
+use sc_cli::{CliConfiguration, DatabaseParams, Result, SharedParams};
+use sc_client_api::StorageProvider;
+use serde_json::json;
+use sp_api::{Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::twox_128, storage::StorageKey, Bytes};
+use sp_runtime::generic::BlockId;
+use std::sync::Arc;
+use structopt::StructOpt;
+
+/// Number of keys fetched per `state_getKeysPaged` request.
+const PAGE_SIZE: u32 = 1000;
+
+/// The `query` command used to read a storage item by its pallet and name.
+///
+/// The storage key is built with the metadata of the runtime, from the names and from the keys of
+/// a map given as arguments. The value is printed as decoded JSON. If some keys of a map are left
+/// out, every entry under the given ones is printed instead, one JSON object per line.
+///
+/// The storage is read from a running node, or with `--offline` from the database of a stopped
+/// one.
+#[derive(Debug, StructOpt)]
+pub struct QueryCmd {
+	/// Pallet of the storage item, e.g. `TemplateModule`.
+	pub pallet: String,
+
+	/// Name of the storage item, e.g. `Something`.
+	pub item: String,
+
+	/// Keys of a map, in order, parsed like the arguments of `tx`.
+	pub keys: Vec<String>,
+
+	/// Hash of the block to read the storage at, instead of the best block.
+	#[structopt(long, parse(try_from_str = parse_hash))]
+	pub at: Option<Hash>,
+
+	/// WebSocket RPC endpoint of the node.
+	#[structopt(long, default_value = "ws://127.0.0.1:9944")]
+	pub url: String,
+
+	/// Read the database of `--base-path` and `--chain` instead of asking a running node.
+	#[structopt(long)]
+	pub offline: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl QueryCmd {
+	/// Run the query command against a running node
+	pub fn run(&self) -> Result<()> {
+		tokio::runtime::Runtime::new()?.block_on(async {
+			let remote = Remote::connect(&self.url).await?;
+			self.query(Source::Remote(remote)).await
+		})
+	}
+
+	/// Run the query command against the database of `client`
+	pub async fn run_offline(&self, client: Arc<FullClient>) -> Result<()> {
+		self.query(Source::Local(client)).await
+	}
+
+	async fn query(&self, source: Source) -> Result<()> {
+		let at = match self.at {
+			Some(at) => at,
+			None => source.best_hash().await?,
+		};
+		let metadata = source.metadata(at).await?;
+		let pallet = metadata
+			.pallet(&self.pallet)
+			.ok_or_else(|| format!("Unknown pallet `{}`", self.pallet))?;
+		let item =
+			pallet.storage.iter().find(|item| item.name == self.item).ok_or_else(|| {
+				format!("Unknown storage item `{}` of {}", self.item, self.pallet)
+			})?;
+		if self.keys.len() > item.keys.len() {
+			return Err(format!(
+				"{}::{} takes {} key(s), got {}",
+				self.pallet,
+				self.item,
+				item.keys.len(),
+				self.keys.len()
+			)
+			.into())
+		}
+
+		let mut prefix =
+			[twox_128(pallet.storage_prefix.as_bytes()), twox_128(item.name.as_bytes())].concat();
+		let encoder = Encoder::new(&metadata);
+		for ((hasher, ty), key) in item.keys.iter().zip(&self.keys) {
+			let mut encoded = Vec::new();
+			encoder
+				.encode(ty, &parse_arg(key), &mut encoded)
+				.map_err(|e| format!("{}::{}: {}", self.pallet, self.item, e))?;
+			prefix.extend(hash(hasher, &encoded));
+		}
+
+		if self.keys.len() == item.keys.len() {
+			let value = source.storage(&prefix, at).await?;
+			println!("{}", decode_value(&metadata, item, value)?);
+			return Ok(())
+		}
+
+		let given = self.keys.len();
+		for key in source.keys(&prefix, at).await? {
+			let keys = decode_keys(&metadata, &item.keys[given..], &mut &key[prefix.len()..])?;
+			let value = source.storage(&key, at).await?;
+			let value = decode_value(&metadata, item, value)?;
+			println!("{}", json!({ "keys": keys, "value": value }));
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for QueryCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Where the storage is read from.
+enum Source {
+	/// A running node.
+	Remote(Remote),
+	/// The database of a node.
+	Local(Arc<FullClient>),
+}
+
+impl Source {
+	async fn best_hash(&self) -> Result<Hash> {
+		match self {
+			Source::Remote(remote) => remote.request("chain_getBlockHash", vec![]).await,
+			Source::Local(client) => Ok(client.info().best_hash),
+		}
+	}
+
+	async fn metadata(&self, at: Hash) -> Result<Metadata> {
+		match self {
+			Source::Remote(remote) => remote.metadata(Some(at)).await,
+			Source::Local(client) => {
+				let metadata = client
+					.runtime_api()
+					.metadata(&BlockId::Hash(at))
+					.map_err(|e| format!("Unable to read the metadata: {}", e))?;
+				Ok(Metadata::decode(&metadata)?)
+			},
+		}
+	}
+
+	async fn storage(&self, key: &[u8], at: Hash) -> Result<Option<Vec<u8>>> {
+		match self {
+			Source::Remote(remote) => remote.storage(key, Some(at)).await,
+			Source::Local(client) => Ok(StorageProvider::<Block, FullBackend>::storage(
+				&**client,
+				&BlockId::Hash(at),
+				&StorageKey(key.to_vec()),
+			)?
+			.map(|value| value.0)),
+		}
+	}
+
+	/// Keys starting with `prefix`, in order.
+	async fn keys(&self, prefix: &[u8], at: Hash) -> Result<Vec<Vec<u8>>> {
+		match self {
+			Source::Remote(remote) => {
+				let mut keys = Vec::new();
+				loop {
+					let page: Vec<StorageKey> = remote
+						.request(
+							"state_getKeysPaged",
+							vec![
+								json!(Bytes(prefix.to_vec())),
+								json!(PAGE_SIZE),
+								json!(keys.last().cloned().map(Bytes)),
+								json!(at),
+							],
+						)
+						.await?;
+					let done = page.len() < PAGE_SIZE as usize;
+					keys.extend(page.into_iter().map(|key| key.0));
+					if done {
+						return Ok(keys)
+					}
+				}
+			},
+			Source::Local(client) => Ok(StorageProvider::<Block, FullBackend>::storage_keys(
+				&**client,
+				&BlockId::Hash(at),
+				&StorageKey(prefix.to_vec()),
+			)?
+			.into_iter()
+			.map(|key| key.0)
+			.collect()),
+		}
+	}
+}
@@ -0,0 +1,74 @@
+//! Access to a running node over its WebSocket RPC server, for the subcommands talking to one.
+
+use crate::metadata::Metadata;
+use jsonrpsee_ws_client::{
+	types::{traits::Client, v2::params::JsonRpcParams},
+	WsClient, WsClientBuilder,
+};
+use node_template_runtime::Hash;
+use sc_cli::Result;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sp_core::{storage::StorageKey, Bytes};
+use sp_version::RuntimeVersion;
+
+/// A connection to a node.
+pub struct Remote {
+	client: WsClient,
+}
+
+impl Remote {
+	/// Connect to the node at `url`, e.g. `ws://127.0.0.1:9944`.
+	pub async fn connect(url: &str) -> Result<Self> {
+		let client = WsClientBuilder::default()
+			.build(url)
+			.await
+			.map_err(|e| format!("Unable to connect to {}: {}", url, e))?;
+		Ok(Self { client })
+	}
+
+	/// The underlying client, e.g. to subscribe.
+	pub fn client(&self) -> &WsClient {
+		&self.client
+	}
+
+	/// Call the RPC `method`.
+	pub async fn request<R: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: Vec<Value>,
+	) -> Result<R> {
+		let params =
+			if params.is_empty() { JsonRpcParams::NoParams } else { JsonRpcParams::Array(params) };
+		self.client
+			.request(method, params)
+			.await
+			.map_err(|e| format!("{} failed: {}", method, e).into())
+	}
+
+	/// Hash of block `number` of the best chain.
+	pub async fn block_hash(&self, number: u32) -> Result<Hash> {
+		self.request::<Option<Hash>>("chain_getBlockHash", vec![json!(number)])
+			.await?
+			.ok_or_else(|| format!("Unknown block #{}", number).into())
+	}
+
+	/// Metadata of the runtime of block `at`, or of the best block.
+	pub async fn metadata(&self, at: Option<Hash>) -> Result<Metadata> {
+		let metadata: Bytes = self.request("state_getMetadata", vec![json!(at)]).await?;
+		Ok(Metadata::decode(&metadata)?)
+	}
+
+	/// Version of the runtime of block `at`, or of the best block.
+	pub async fn runtime_version(&self, at: Option<Hash>) -> Result<RuntimeVersion> {
+		self.request("state_getRuntimeVersion", vec![json!(at)]).await
+	}
+
+	/// Value of `key` at block `at`, or at the best block.
+	pub async fn storage(&self, key: &[u8], at: Option<Hash>) -> Result<Option<Vec<u8>>> {
+		let key = StorageKey(key.to_vec());
+		let value: Option<Bytes> =
+			self.request("state_getStorage", vec![json!(key), json!(at)]).await?;
+		Ok(value.map(|value| value.0))
+	}
+}
@@ -0,0 +1,365 @@
+use super::storage::Items;
+use crate::{
+	decoder::{events_key, DecodedEvent, DecodedExtrinsic, Decoder},
+	dispatch_error::DecodedError,
+	metadata::Metadata,
+	service::{FullBackend, FullClient},
+};
+use codec::Encode;
+use node_template_runtime::{opaque::Block, BlockNumber, Hash};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, ImportParams, Result, SharedParams};
+use sc_client_api::{BlockBackend, StorageProvider};
+use serde::Serialize;
+use serde_json::Value;
+use sp_api::{Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_block_builder::BlockBuilder;
+use sp_core::{bytes::from_hex, hashing::blake2_256, storage::StorageKey, Bytes, ExecutionContext};
+use sp_runtime::{
+	generic::{BlockId, DigestItem},
+	traits::{Block as BlockT, Header as _},
+};
+use std::{
+	collections::HashMap,
+	fmt,
+	fs::File,
+	io::{self, Write},
+	mem,
+	path::PathBuf,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+use structopt::StructOpt;
+use tracing::{
+	field::{Field, Visit},
+	span, Dispatch, Event, Subscriber,
+};
+
+/// The `replay-block` command used to re-execute a block and trace its storage accesses.
+///
+/// The block is executed again on top of the state of its parent, which must not have been
+/// pruned, with `--execution Wasm` or `--execution Native`. Every storage read and write is
+/// recorded with its key, the storage item the key belongs to, and the size and hash of the value,
+/// along with the time it took. They are grouped by phase: the initialization of the block, each
+/// extrinsic, and the finalization. The report is written as JSON.
+///
+/// Accesses are recorded from the `state` traces of the host, so reads and writes served by the
+/// runtime's own caches are not seen. With `--execution Both`, the block is executed twice and
+/// every access is recorded twice.
+#[derive(Debug, StructOpt)]
+pub struct ReplayBlockCmd {
+	/// Hash or number of the block to replay.
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub block: BlockNumberOrHash,
+
+	/// Path of the report to write, instead of stdout.
+	#[structopt(long, short = "o", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+/// Report of a replayed block.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+	number: BlockNumber,
+	hash: Hash,
+	/// Whether the replay resulted in the state root of the block.
+	state_root_matches: bool,
+	/// Time it took to execute the whole block.
+	time_ns: u64,
+	phases: Vec<Phase>,
+}
+
+/// The initialization, an extrinsic or the finalization of the replayed block.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Phase {
+	/// `initialize`, `extrinsic` or `finalize`.
+	phase: &'static str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	index: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	hash: Option<Hash>,
+	#[serde(flatten)]
+	extrinsic: Option<DecodedExtrinsic>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	success: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	error: Option<DecodedError>,
+	/// Weight of the extrinsic, as reported by its `ExtrinsicSuccess` or `ExtrinsicFailed` event.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	weight: Option<Value>,
+	time_ns: u64,
+	reads: Vec<Access>,
+	writes: Vec<Access>,
+}
+
+/// A storage access of a [`Phase`].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Access {
+	/// Host function of the access, as traced by `sp-state-machine`, e.g. `Get` or `Put`.
+	method: String,
+	/// Key, or prefix for `ClearPrefix`.
+	key: Bytes,
+	/// Storage key of the child trie, for the accesses to one.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	child: Option<Bytes>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pallet: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	item: Option<String>,
+	/// Size of the value read or written, `None` if there is none.
+	size: Option<usize>,
+	/// `blake2_256` of the value read or written.
+	value_hash: Option<Hash>,
+}
+
+impl ReplayBlockCmd {
+	/// Run the replay-block command
+	pub async fn run(&self, client: Arc<FullClient>) -> Result<()> {
+		let id = self.block.parse::<Block>()?;
+		let block = client.block(&id)?.ok_or_else(|| format!("Block {} not found", id))?.block;
+		let hash = block.hash();
+		let (mut header, extrinsics) = block.deconstruct();
+		let number = *header.number();
+		let parent = BlockId::Hash(*header.parent_hash());
+		// The seal is added to the header by the consensus once the block is executed.
+		if let Some(DigestItem::Seal(..)) = header.digest().logs().last() {
+			header.digest_mut().pop();
+		}
+
+		let metadata = client.runtime_api().metadata(&parent).map_err(|e| {
+			format!("State of the parent of block #{} is not available: {}", number, e)
+		})?;
+		let metadata = Metadata::decode(&metadata)?;
+		let items = Items::new(&metadata);
+		let decoder = Decoder::new(&metadata);
+		// Only used for the weights, the state of the block itself may be pruned.
+		let events = StorageProvider::<Block, FullBackend>::storage(
+			&*client,
+			&BlockId::Hash(hash),
+			&StorageKey(events_key()),
+		)
+		.ok()
+		.flatten()
+		.and_then(|events| decoder.events(&mut &events.0[..]).ok())
+		.unwrap_or_default();
+
+		let recorder = Recorder::default();
+		let api = client.runtime_api();
+		let start = Instant::now();
+
+		let (result, time, (reads, writes)) = recorder.trace(&items, || {
+			api.initialize_block_with_context(&parent, ExecutionContext::Importing, &header)
+		});
+		result.map_err(|e| format!("Unable to initialize block #{}: {}", number, e))?;
+		let mut phases = vec![Phase::new("initialize", time, reads, writes)];
+
+		for (index, xt) in extrinsics.into_iter().enumerate() {
+			let encoded = xt.encode();
+			let (result, time, (reads, writes)) = recorder.trace(&items, || {
+				api.apply_extrinsic_with_context(&parent, ExecutionContext::Importing, xt)
+			});
+			let result = result.map_err(|e| {
+				format!("Unable to apply extrinsic #{} of block #{}: {}", index, number, e)
+			})?;
+			let (success, error) = match result {
+				Ok(Ok(())) => (true, None),
+				Ok(Err(e)) => (false, Some(DecodedError::new(&e, &metadata))),
+				Err(e) => return Err(format!("Extrinsic #{} is invalid: {:?}", index, e).into()),
+			};
+			let mut phase = Phase::new("extrinsic", time, reads, writes);
+			phase.index = Some(index as u32);
+			phase.hash = Some(Hash::from(blake2_256(&encoded)));
+			phase.extrinsic = decoder.extrinsic(&mut &encoded[..], &mut Vec::new()).ok();
+			phase.success = Some(success);
+			phase.error = error;
+			phase.weight = weight(&events, index as u32);
+			phases.push(phase);
+		}
+
+		let (result, time, (reads, writes)) = recorder.trace(&items, || {
+			api.finalize_block_with_context(&parent, ExecutionContext::Importing)
+		});
+		let replayed =
+			result.map_err(|e| format!("Unable to finalize block #{}: {}", number, e))?;
+		phases.push(Phase::new("finalize", time, reads, writes));
+
+		let state_root_matches = replayed.state_root() == header.state_root();
+		if !state_root_matches {
+			log::warn!(
+				"Replaying block #{} resulted in state root {}, expected {}",
+				number,
+				replayed.state_root(),
+				header.state_root()
+			);
+		}
+		let report = Report {
+			number,
+			hash,
+			state_root_matches,
+			time_ns: start.elapsed().as_nanos() as u64,
+			phases,
+		};
+
+		let mut output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(io::stdout()),
+		};
+		serde_json::to_writer_pretty(&mut output, &report).map_err(|e| e.to_string())?;
+		writeln!(output)?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ReplayBlockCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+impl Phase {
+	fn new(phase: &'static str, time: Duration, reads: Vec<Access>, writes: Vec<Access>) -> Self {
+		Phase {
+			phase,
+			index: None,
+			hash: None,
+			extrinsic: None,
+			success: None,
+			error: None,
+			weight: None,
+			time_ns: time.as_nanos() as u64,
+			reads,
+			writes,
+		}
+	}
+}
+
+/// Weight of extrinsic `index`, from the `DispatchInfo` of its `System` event.
+fn weight(events: &[(DecodedEvent, Vec<sp_runtime::AccountId32>)], index: u32) -> Option<Value> {
+	events
+		.iter()
+		.map(|(event, _)| event)
+		.find(|event| {
+			event.extrinsic == Some(index) &&
+				event.pallet == "System" &&
+				(event.name == "ExtrinsicSuccess" || event.name == "ExtrinsicFailed")
+		})
+		.and_then(|event| event.args.last())
+		.map(|info| info["weight"].clone())
+}
+
+/// Subscriber recording the fields of the `state` traces, emitted by `sp-state-machine` for every
+/// storage access of the runtime.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<HashMap<&'static str, String>>>>);
+
+impl Recorder {
+	/// Run `f` while recording the storage accesses, returns how long it took and the accesses
+	/// split into reads and writes.
+	fn trace<R>(
+		&self,
+		items: &Items,
+		f: impl FnOnce() -> R,
+	) -> (R, Duration, (Vec<Access>, Vec<Access>)) {
+		let start = Instant::now();
+		let result = tracing::dispatcher::with_default(&Dispatch::new(self.clone()), f);
+		(result, start.elapsed(), self.take(items))
+	}
+
+	/// Accesses recorded since the last call, split into reads and writes.
+	fn take(&self, items: &Items) -> (Vec<Access>, Vec<Access>) {
+		let traces = mem::take(&mut *self.0.lock().expect("not poisoned; qed"));
+		let mut reads = Vec::new();
+		let mut writes = Vec::new();
+		for fields in traces {
+			let method = match fields.get("method") {
+				Some(method) => method.clone(),
+				None => continue,
+			};
+			let (write, value) = match method.trim_start_matches("Child") {
+				"Get" => (false, fields.get("result")),
+				"Put" | "Append" => (true, fields.get("value")),
+				"ClearPrefix" | "Kill" => (true, None),
+				// Storage roots are computed, not read.
+				method if method.contains("Root") => continue,
+				_ => (false, None),
+			};
+			let key = fields
+				.get("key")
+				.or_else(|| fields.get("prefix"))
+				.and_then(|key| from_hex(key).ok())
+				.unwrap_or_default();
+			let value = value.and_then(|value| parse_value(value));
+			let child = fields.get("child_info").and_then(|child| from_hex(child).ok());
+			let item = if child.is_none() { items.get(&key) } else { None };
+			let access = Access {
+				method,
+				pallet: item.map(|(pallet, _)| pallet.name.clone()),
+				item: item.map(|(_, item)| item.name.clone()),
+				key: Bytes(key),
+				child: child.map(Bytes),
+				size: value.as_ref().map(Vec::len),
+				value_hash: value.map(|value| Hash::from(blake2_256(&value))),
+			};
+			if write {
+				writes.push(access);
+			} else {
+				reads.push(access);
+			}
+		}
+		(reads, writes)
+	}
+}
+
+impl Subscriber for Recorder {
+	fn enabled(&self, metadata: &tracing::Metadata<'_>) -> bool {
+		metadata.target() == "state"
+	}
+
+	fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+		span::Id::from_u64(1)
+	}
+
+	fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+	fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+	fn event(&self, event: &Event<'_>) {
+		let mut fields = Fields::default();
+		event.record(&mut fields);
+		self.0.lock().expect("not poisoned; qed").push(fields.0);
+	}
+
+	fn enter(&self, _: &span::Id) {}
+
+	fn exit(&self, _: &span::Id) {}
+}
+
+/// Fields of a trace, formatted.
+#[derive(Default)]
+struct Fields(HashMap<&'static str, String>);
+
+impl Visit for Fields {
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		self.0.insert(field.name(), format!("{:?}", value));
+	}
+}
+
+/// Parse a traced value: hex, optionally wrapped in `Some(..)`, or `None`.
+fn parse_value(value: &str) -> Option<Vec<u8>> {
+	let value = value.strip_prefix("Some(").and_then(|v| v.strip_suffix(')')).unwrap_or(value);
+	from_hex(value).ok()
+}
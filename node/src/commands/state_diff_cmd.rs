@@ -0,0 +1,169 @@
+use super::storage::{decode_keys, decode_value, parse_hash, Items};
+use crate::{
+	metadata::Metadata,
+	service::{FullBackend, FullClient},
+};
+use node_template_runtime::{opaque::Block, Hash};
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, Result, SharedParams};
+use sc_client_api::StorageProvider;
+use serde_json::{json, Map, Value};
+use sp_api::{Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_core::{
+	hashing::{blake2_256, twox_128},
+	storage::{well_known_keys, StorageKey},
+	Bytes,
+};
+use sp_runtime::generic::BlockId;
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	sync::Arc,
+};
+use structopt::StructOpt;
+
+/// The `state-diff` command used to list the storage changed between two blocks.
+///
+/// Both states are read from the database of a stopped node, so they must not have been pruned.
+/// Every added, removed and modified key is printed as one JSON object per line, in the order of
+/// the keys. Keys of storage items are resolved to their pallet, item and map keys, and values are
+/// decoded with the metadata of the block they were read at. Other values are printed as hex, and
+/// the runtime code as its hash.
+///
+/// Only the main trie is compared. A changed child trie shows up as a modified root in the main
+/// trie.
+#[derive(Debug, StructOpt)]
+pub struct StateDiffCmd {
+	/// Hash of the block to compare from.
+	#[structopt(long, parse(try_from_str = parse_hash))]
+	pub from: Hash,
+
+	/// Hash of the block to compare to.
+	#[structopt(long, parse(try_from_str = parse_hash))]
+	pub to: Hash,
+
+	/// Only compare the storage of this pallet, e.g. `System`. May be given several times.
+	#[structopt(long = "pallet", value_name = "PALLET")]
+	pub pallets: Vec<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl StateDiffCmd {
+	/// Run the state-diff command
+	pub async fn run(&self, client: Arc<FullClient>) -> Result<()> {
+		let from_metadata = metadata(&client, self.from)?;
+		let to_metadata = metadata(&client, self.to)?;
+		let from_items = Items::new(&from_metadata);
+		let to_items = Items::new(&to_metadata);
+
+		let mut prefixes = Vec::new();
+		for name in &self.pallets {
+			let pallet = to_metadata
+				.pallet(name)
+				.or_else(|| from_metadata.pallet(name))
+				.ok_or_else(|| format!("Unknown pallet `{}`", name))?;
+			prefixes.push(twox_128(pallet.storage_prefix.as_bytes()).to_vec());
+		}
+		if prefixes.is_empty() {
+			prefixes.push(Vec::new());
+		}
+
+		let from = pairs(&client, self.from, &prefixes)?;
+		let to = pairs(&client, self.to, &prefixes)?;
+		let keys = from.keys().chain(to.keys()).collect::<BTreeSet<_>>();
+		for key in keys {
+			let (change, old, new) = match (from.get(key), to.get(key)) {
+				(None, Some(new)) => ("added", None, Some(new)),
+				(Some(old), None) => ("removed", Some(old), None),
+				(Some(old), Some(new)) if old != new => ("modified", Some(old), Some(new)),
+				_ => continue,
+			};
+
+			let mut line = Map::new();
+			line.insert("change".into(), json!(change));
+			line.insert("key".into(), json!(Bytes(key.clone())));
+			// Names and map keys as of the latest block the key exists at.
+			let (items, metadata) = match to_items.get(key) {
+				Some(_) => (&to_items, &to_metadata),
+				None => (&from_items, &from_metadata),
+			};
+			if let Some((pallet, item)) = items.get(key) {
+				line.insert("pallet".into(), json!(pallet.name));
+				line.insert("item".into(), json!(item.name));
+				if !item.keys.is_empty() {
+					let keys = decode_keys(metadata, &item.keys, &mut &key[32..]).ok();
+					line.insert("keys".into(), json!(keys));
+				}
+			}
+			if let Some(old) = old {
+				line.insert("from".into(), value(&from_metadata, &from_items, key, old));
+			}
+			if let Some(new) = new {
+				line.insert("to".into(), value(&to_metadata, &to_items, key, new));
+			}
+			println!("{}", Value::Object(line));
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for StateDiffCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+fn metadata(client: &FullClient, at: Hash) -> Result<Metadata> {
+	let metadata = client
+		.runtime_api()
+		.metadata(&BlockId::Hash(at))
+		.map_err(|e| format!("Unable to read the metadata at {}: {}", at, e))?;
+	Ok(Metadata::decode(&metadata)?)
+}
+
+/// Key-value pairs of the state of block `at` under any of `prefixes`.
+fn pairs(
+	client: &FullClient,
+	at: Hash,
+	prefixes: &[Vec<u8>],
+) -> Result<BTreeMap<Vec<u8>, Vec<u8>>> {
+	let mut pairs = BTreeMap::new();
+	for prefix in prefixes {
+		let found = StorageProvider::<Block, FullBackend>::storage_pairs(
+			client,
+			&BlockId::Hash(at),
+			&StorageKey(prefix.clone()),
+		)
+		.map_err(|e| format!("State of block {} is not available: {}", at, e))?;
+		pairs.extend(found.into_iter().map(|(key, value)| (key.0, value.0)));
+	}
+	Ok(pairs)
+}
+
+/// A stored value, decoded if its storage item is known.
+fn value(metadata: &Metadata, items: &Items, key: &[u8], value: &[u8]) -> Value {
+	if key == well_known_keys::CODE {
+		return json!({ "blake2_256": Bytes(blake2_256(value).to_vec()) })
+	}
+	items
+		.get(key)
+		.and_then(|(_, item)| decode_value(metadata, item, Some(value.to_vec())).ok())
+		.unwrap_or_else(|| json!(Bytes(value.to_vec())))
+}
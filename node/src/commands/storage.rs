@@ -0,0 +1,102 @@
+//! Storage keys and values of the pallets, for the subcommands reading storage.
+
+use crate::{
+	decoder::Decoder,
+	metadata::{Metadata, PalletMetadata, StorageEntryModifier, StorageHasher, StorageMetadata},
+};
+use node_template_runtime::Hash;
+use sc_cli::Result;
+use serde_json::{json, Value};
+use sp_core::{
+	hashing::{blake2_128, blake2_256, twox_128, twox_256, twox_64},
+	Bytes,
+};
+use std::{collections::HashMap, str::FromStr};
+
+/// Storage items of a runtime, by the prefix of their keys.
+pub struct Items<'a>(HashMap<Vec<u8>, (&'a PalletMetadata, &'a StorageMetadata)>);
+
+impl<'a> Items<'a> {
+	/// Storage items of the runtime of `metadata`.
+	pub fn new(metadata: &'a Metadata) -> Self {
+		let mut items = HashMap::new();
+		for pallet in &metadata.pallets {
+			for item in &pallet.storage {
+				let prefix =
+					[twox_128(pallet.storage_prefix.as_bytes()), twox_128(item.name.as_bytes())]
+						.concat();
+				items.insert(prefix, (pallet, item));
+			}
+		}
+		Self(items)
+	}
+
+	/// Pallet and storage item of `key`.
+	pub fn get(&self, key: &[u8]) -> Option<(&'a PalletMetadata, &'a StorageMetadata)> {
+		key.get(..32).and_then(|prefix| self.0.get(prefix)).copied()
+	}
+}
+
+/// Hash an encoded key of a map.
+pub fn hash(hasher: &StorageHasher, encoded: &[u8]) -> Vec<u8> {
+	match hasher {
+		StorageHasher::Blake2_128 => blake2_128(encoded).to_vec(),
+		StorageHasher::Blake2_256 => blake2_256(encoded).to_vec(),
+		StorageHasher::Blake2_128Concat => [&blake2_128(encoded)[..], encoded].concat(),
+		StorageHasher::Twox128 => twox_128(encoded).to_vec(),
+		StorageHasher::Twox256 => twox_256(encoded).to_vec(),
+		StorageHasher::Twox64Concat => [&twox_64(encoded)[..], encoded].concat(),
+		StorageHasher::Identity => encoded.to_vec(),
+	}
+}
+
+/// Decode the keys of a map from the end of a storage key.
+///
+/// Keys hashed without their value appended are printed as their hash.
+pub fn decode_keys(
+	metadata: &Metadata,
+	keys: &[(StorageHasher, String)],
+	input: &mut &[u8],
+) -> Result<Vec<Value>> {
+	let decoder = Decoder::new(metadata);
+	let mut decoded = Vec::new();
+	for (hasher, ty) in keys {
+		let (len, concat) = match hasher {
+			StorageHasher::Blake2_128 | StorageHasher::Twox128 => (16, false),
+			StorageHasher::Blake2_256 | StorageHasher::Twox256 => (32, false),
+			StorageHasher::Blake2_128Concat => (16, true),
+			StorageHasher::Twox64Concat => (8, true),
+			StorageHasher::Identity => (0, true),
+		};
+		if input.len() < len {
+			return Err("Storage key is too short".into())
+		}
+		let (hashed, rest) = input.split_at(len);
+		*input = rest;
+		decoded.push(if concat {
+			decoder.decode(ty, input, &mut Vec::new())?
+		} else {
+			json!(Bytes(hashed.to_vec()))
+		});
+	}
+	Ok(decoded)
+}
+
+/// Decode a stored value, falling back to the default of the item if there is none.
+pub fn decode_value(
+	metadata: &Metadata,
+	item: &StorageMetadata,
+	value: Option<Vec<u8>>,
+) -> Result<Value> {
+	let value = match (value, &item.modifier) {
+		(Some(value), _) => value,
+		(None, StorageEntryModifier::Default) => item.default.clone(),
+		(None, StorageEntryModifier::Optional) => return Ok(Value::Null),
+	};
+	Ok(Decoder::new(metadata).decode(&item.value, &mut &value[..], &mut Vec::new())?)
+}
+
+/// Parse a block hash given on the command line, with or without `0x`.
+pub fn parse_hash(hash: &str) -> std::result::Result<Hash, String> {
+	Hash::from_str(hash.trim_start_matches("0x")).map_err(|e| format!("Invalid hash: {:?}", e))
+}
@@ -0,0 +1,283 @@
+use super::remote::Remote;
+use crate::{
+	decoder::{events_key, Decoder},
+	dispatch_error::DecodedError,
+	encoder::{parse_arg, Encoder},
+};
+use codec::{Compact, Encode};
+use jsonrpsee_ws_client::types::{traits::SubscriptionClient, v2::params::JsonRpcParams};
+use node_template_runtime::{Hash, Header};
+use sc_cli::{Error, Result};
+use sc_keystore::LocalKeystore;
+use sc_transaction_pool_api::TransactionStatus;
+use serde_json::{json, Value};
+use sp_core::{
+	crypto::{KeyTypeId, Pair as _, SecretString, Ss58Codec},
+	hashing::blake2_256,
+	sr25519, Bytes,
+};
+use sp_keystore::SyncCryptoStore;
+use sp_runtime::{generic::Era, traits::Header as _};
+use std::{convert::TryFrom, path::PathBuf};
+use structopt::StructOpt;
+
+/// Bit of the extrinsic version byte flagging a signed extrinsic.
+const SIGNED_FLAG: u8 = 0b1000_0000;
+
+/// The `tx` command used to sign an extrinsic and submit it to a running node.
+///
+/// The call is encoded, and the extrinsic built with the signed extensions, of the metadata of the
+/// node's runtime, so that the extrinsic stays valid across runtime upgrades. The nonce and the
+/// genesis hash are fetched from the node too. The command then watches the extrinsic until its
+/// block is finalized, printing the events it emitted, and fails if its dispatch failed.
+#[derive(Debug, StructOpt)]
+pub struct TxCmd {
+	/// Pallet of the call, e.g. `TemplateModule`.
+	pub pallet: String,
+
+	/// Name of the call, e.g. `do_something`.
+	pub call: String,
+
+	/// Arguments of the call, in order.
+	///
+	/// Each one is parsed as JSON, or taken as a string if it isn't valid JSON, e.g. an SS58
+	/// address. Nested calls, e.g. of `Sudo::sudo`, are given as
+	/// `{"pallet": "TemplateModule", "name": "do_something", "args": [42]}`.
+	pub args: Vec<String>,
+
+	/// WebSocket RPC endpoint of the node.
+	#[structopt(long, default_value = "ws://127.0.0.1:9944")]
+	pub url: String,
+
+	/// Secret URI of the sr25519 key to sign with, e.g. `//Alice` or a mnemonic.
+	#[structopt(long, required_unless = "keystore-path", conflicts_with = "keystore-path")]
+	pub suri: Option<String>,
+
+	/// Keystore holding the sr25519 key to sign with.
+	#[structopt(long, parse(from_os_str))]
+	pub keystore_path: Option<PathBuf>,
+
+	/// Password of the keystore, or of the secret URI.
+	#[structopt(long)]
+	pub password: Option<String>,
+
+	/// SS58 address of the key to sign with, if the keystore holds several keys of `--key-type`.
+	#[structopt(long)]
+	pub signer: Option<String>,
+
+	/// Type of the key to sign with in the keystore.
+	#[structopt(long, default_value = "acco")]
+	pub key_type: String,
+
+	/// Nonce to sign with, instead of the next one of the signer.
+	#[structopt(long)]
+	pub nonce: Option<u32>,
+
+	/// Tip for the block author.
+	#[structopt(long, default_value = "0")]
+	pub tip: u128,
+
+	/// Number of blocks the extrinsic is valid for, rounded to a power of two. `0` makes it
+	/// immortal.
+	#[structopt(long, default_value = "64")]
+	pub mortality: u64,
+}
+
+impl TxCmd {
+	/// Run the tx command
+	pub fn run(&self) -> Result<()> {
+		tokio::runtime::Runtime::new()?.block_on(self.submit())
+	}
+
+	async fn submit(&self) -> Result<()> {
+		let signer = self.signer()?;
+		let remote = Remote::connect(&self.url).await?;
+		let metadata = remote.metadata(None).await?;
+		let version = remote.runtime_version(None).await?;
+		let genesis_hash = remote.block_hash(0).await?;
+
+		let args = json!(self.args.iter().map(|arg| parse_arg(arg)).collect::<Vec<_>>());
+		let call = Encoder::new(&metadata).call(&self.pallet, &self.call, &args)?;
+		let account = signer.public().to_ss58check();
+		let nonce = match self.nonce {
+			Some(nonce) => nonce,
+			None => remote.request("system_accountNextIndex", vec![json!(account)]).await?,
+		};
+		let (era, era_hash) = if self.mortality == 0 {
+			(Era::Immortal, genesis_hash)
+		} else {
+			let best: Header = remote.request("chain_getHeader", vec![]).await?;
+			let era = Era::mortal(self.mortality, *best.number() as u64);
+			(era, remote.block_hash(era.birth(*best.number() as u64) as u32).await?)
+		};
+
+		// The explicit part of the signed extensions goes into the extrinsic, the implicit part is
+		// only signed.
+		let mut extra = Vec::new();
+		let mut implicit = Vec::new();
+		for extension in &metadata.signed_extensions {
+			match extension.as_str() {
+				"CheckSpecVersion" => version.spec_version.encode_to(&mut implicit),
+				"CheckTxVersion" => version.transaction_version.encode_to(&mut implicit),
+				"CheckGenesis" => genesis_hash.encode_to(&mut implicit),
+				"CheckMortality" | "CheckEra" => {
+					era.encode_to(&mut extra);
+					era_hash.encode_to(&mut implicit);
+				},
+				"CheckNonce" => Compact(nonce).encode_to(&mut extra),
+				"CheckWeight" => (),
+				"ChargeTransactionPayment" => Compact(self.tip).encode_to(&mut extra),
+				extension =>
+					return Err(format!("Unsupported signed extension `{}`", extension).into()),
+			}
+		}
+
+		let payload = [&call[..], &extra, &implicit].concat();
+		let signature = if payload.len() > 256 {
+			signer.sign(&blake2_256(&payload))?
+		} else {
+			signer.sign(&payload)?
+		};
+		let mut xt = vec![SIGNED_FLAG | metadata.extrinsic_version];
+		// `MultiAddress::Id` and `MultiSignature::Sr25519`
+		(0u8, signer.public()).encode_to(&mut xt);
+		(1u8, signature).encode_to(&mut xt);
+		xt.extend(extra);
+		xt.extend(call);
+		let xt = xt.encode();
+		let hash = Hash::from(blake2_256(&xt));
+
+		println!("Submitting {} signed by {} with nonce {}", hash, account, nonce);
+		let mut statuses = remote
+			.client()
+			.subscribe::<TransactionStatus<Hash, Hash>>(
+				"author_submitAndWatchExtrinsic",
+				JsonRpcParams::Array(vec![json!(Bytes(xt))]),
+				"author_unwatchExtrinsic",
+			)
+			.await
+			.map_err(|e| format!("Unable to submit the extrinsic: {}", e))?;
+
+		let mut error = None;
+		while let Some(status) = statuses
+			.next()
+			.await
+			.map_err(|e| format!("Unable to watch the extrinsic: {}", e))?
+		{
+			match status {
+				TransactionStatus::Future => println!("Waiting for an earlier nonce"),
+				TransactionStatus::Ready => println!("Ready"),
+				TransactionStatus::Broadcast(peers) =>
+					println!("Broadcast to {} peer(s)", peers.len()),
+				TransactionStatus::InBlock(block) => {
+					println!("In block {}", block);
+					error = report(&remote, block, hash).await?;
+				},
+				TransactionStatus::Retracted(block) => println!("Block {} was retracted", block),
+				TransactionStatus::Finalized(block) => {
+					println!("Finalized in block {}", block);
+					return match error {
+						Some(error) => Err(Error::Input(format!("Dispatch failed: {}", error))),
+						None => Ok(()),
+					}
+				},
+				status => return Err(format!("Extrinsic not included: {:?}", status).into()),
+			}
+		}
+		Err("The node stopped watching the extrinsic".into())
+	}
+
+	fn signer(&self) -> Result<Signer> {
+		let password = self.password.as_deref();
+		if let Some(suri) = &self.suri {
+			let pair = sr25519::Pair::from_string(suri, password)
+				.map_err(|e| format!("Invalid secret URI: {:?}", e))?;
+			return Ok(Signer::Pair(pair))
+		}
+
+		let path = self.keystore_path.as_ref().expect("required unless `--suri`; qed");
+		let keystore = LocalKeystore::open(path, password.map(|p| SecretString::new(p.into())))
+			.map_err(|e| format!("Unable to open the keystore: {}", e))?;
+		let key_type = KeyTypeId::try_from(self.key_type.as_str())
+			.map_err(|_| format!("Invalid key type `{}`", self.key_type))?;
+		let keys = SyncCryptoStore::sr25519_public_keys(&keystore, key_type);
+		let public = match &self.signer {
+			Some(address) => {
+				let public = sr25519::Public::from_ss58check(address)
+					.map_err(|e| format!("Invalid signer {}: {:?}", address, e))?;
+				if !keys.contains(&public) {
+					return Err(
+						format!("No `{}` key {} in the keystore", self.key_type, address).into()
+					)
+				}
+				public
+			},
+			None => match keys.as_slice() {
+				[public] => *public,
+				[] => return Err(format!("No `{}` key in the keystore", self.key_type).into()),
+				_ => return Err("Several keys in the keystore, pick one with `--signer`".into()),
+			},
+		};
+		Ok(Signer::Keystore(keystore, key_type, public))
+	}
+}
+
+/// An sr25519 key to sign with.
+enum Signer {
+	Pair(sr25519::Pair),
+	Keystore(LocalKeystore, KeyTypeId, sr25519::Public),
+}
+
+impl Signer {
+	fn public(&self) -> sr25519::Public {
+		match self {
+			Signer::Pair(pair) => pair.public(),
+			Signer::Keystore(_, _, public) => *public,
+		}
+	}
+
+	fn sign(&self, payload: &[u8]) -> Result<sr25519::Signature> {
+		match self {
+			Signer::Pair(pair) => Ok(pair.sign(payload)),
+			Signer::Keystore(keystore, key_type, public) => {
+				let signature =
+					SyncCryptoStore::sign_with(keystore, *key_type, &(*public).into(), payload)
+						.map_err(|e| format!("Unable to sign: {}", e))?
+						.ok_or("The signing key is not in the keystore")?;
+				sr25519::Signature::try_from(signature.as_slice())
+					.map_err(|_| "Invalid signature from the keystore".into())
+			},
+		}
+	}
+}
+
+/// Print the events of the extrinsic `hash` in `block`, returns why its dispatch failed.
+async fn report(remote: &Remote, block: Hash, hash: Hash) -> Result<Option<DecodedError>> {
+	let signed: Value = remote.request("chain_getBlock", vec![json!(block)]).await?;
+	let extrinsics: Vec<Bytes> = serde_json::from_value(signed["block"]["extrinsics"].clone())
+		.map_err(|e| format!("Invalid block {}: {}", block, e))?;
+	let index = extrinsics
+		.iter()
+		.position(|xt| Hash::from(blake2_256(xt)) == hash)
+		.ok_or_else(|| format!("Extrinsic {} not found in block {}", hash, block))?;
+	let parent_hash: Hash = serde_json::from_value(signed["block"]["header"]["parentHash"].clone())
+		.map_err(|e| format!("Invalid block {}: {}", block, e))?;
+
+	// The events were deposited by the runtime of the parent block.
+	let metadata = remote.metadata(Some(parent_hash)).await?;
+	let events = remote.storage(&events_key(), Some(block)).await?.unwrap_or_default();
+	let mut error = None;
+	for (event, _) in Decoder::new(&metadata).events(&mut &events[..])? {
+		if event.extrinsic != Some(index as u32) {
+			continue
+		}
+		println!("  {}::{} {}", event.pallet, event.name, Value::from(event.args));
+		if let Some(e) = event.error {
+			println!("    {}", e);
+			if event.pallet == "System" && event.name == "ExtrinsicFailed" {
+				error = Some(e);
+			}
+		}
+	}
+	Ok(error)
+}
@@ -0,0 +1,423 @@
+//! Decoding of SCALE encoded calls, events and storage values into JSON.
+//!
+//! Values are decoded by the type names of the metadata, so that blocks are decoded with the
+//! metadata of the runtime that produced them. Only the types used by the pallets of this runtime
+//! are known, a value of any other type fails to decode.
+//!
+//! Accounts are rendered as SS58 addresses, balances as decimal strings since they may not fit in
+//! a JSON number, and byte vectors and hashes as `0x` prefixed hex.
+
+use crate::{dispatch_error::DecodedError, metadata::Metadata};
+use codec::{Compact, Decode, Input};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sp_core::{crypto::Ss58Codec, hashing::twox_128, Bytes};
+use sp_runtime::{generic::Era, AccountId32, DispatchError, MultiSignature};
+
+/// A call with its arguments.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecodedCall {
+	/// Pallet of the call.
+	pub pallet: String,
+	/// Name of the call.
+	pub name: String,
+	/// Arguments by name.
+	pub args: Map<String, Value>,
+}
+
+/// An extrinsic, with the fields of its signature.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecodedExtrinsic {
+	/// Signer of a signed extrinsic, as an SS58 address for an account.
+	pub signer: Option<String>,
+	/// Nonce of a signed extrinsic.
+	pub nonce: Option<u32>,
+	/// Tip of a signed extrinsic.
+	pub tip: Option<String>,
+	/// The call.
+	pub call: DecodedCall,
+}
+
+/// An event with its arguments.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecodedEvent {
+	/// Index of the extrinsic that emitted the event, `None` during initialization and
+	/// finalization of the block.
+	pub extrinsic: Option<u32>,
+	/// Pallet of the event.
+	pub pallet: String,
+	/// Name of the event.
+	pub name: String,
+	/// Arguments, in order.
+	pub args: Vec<Value>,
+	/// Error reported by the event, e.g. by `System::ExtrinsicFailed`.
+	pub error: Option<DecodedError>,
+}
+
+/// Decodes values with the type names of a [`Metadata`].
+pub struct Decoder<'a> {
+	metadata: &'a Metadata,
+}
+
+impl<'a> Decoder<'a> {
+	/// Create a decoder for the runtime described by `metadata`.
+	pub fn new(metadata: &'a Metadata) -> Self {
+		Self { metadata }
+	}
+
+	/// Decode a value of type `ty`, collecting the accounts it mentions into `accounts`.
+	pub fn decode(
+		&self,
+		ty: &str,
+		input: &mut &[u8],
+		accounts: &mut Vec<AccountId32>,
+	) -> Result<Value, String> {
+		let ty = normalize(ty);
+		let ty = ty.as_str();
+
+		if let Some((name, args)) = generic(ty) {
+			return match (name, args.as_slice()) {
+				("Vec", ["u8"]) => Ok(hex(&Vec::<u8>::decode(input).map_err(err)?)),
+				("Vec", [inner]) => {
+					let len = Compact::<u32>::decode(input).map_err(err)?.0;
+					(0..len).map(|_| self.decode(inner, input, accounts)).collect()
+				},
+				("Option", [inner]) => match u8::decode(input).map_err(err)? {
+					0 => Ok(Value::Null),
+					1 => self.decode(inner, input, accounts),
+					b => Err(format!("Invalid `Option` discriminant {}", b)),
+				},
+				("Box", [inner]) => self.decode(inner, input, accounts),
+				("Compact", ["()"]) => Ok(Value::Null),
+				("Compact", [inner]) => {
+					let n = Compact::<u128>::decode(input).map_err(err)?.0;
+					Ok(if is_u128(inner) { n.to_string().into() } else { (n as u64).into() })
+				},
+				("AccountInfo", [_, data]) => Ok(json!({
+					"nonce": self.decode("u32", input, accounts)?,
+					"consumers": self.decode("u32", input, accounts)?,
+					"providers": self.decode("u32", input, accounts)?,
+					"sufficients": self.decode("u32", input, accounts)?,
+					"data": self.decode(data, input, accounts)?,
+				})),
+				("AccountData", [_]) => Ok(json!({
+					"free": self.decode("Balance", input, accounts)?,
+					"reserved": self.decode("Balance", input, accounts)?,
+					"miscFrozen": self.decode("Balance", input, accounts)?,
+					"feeFrozen": self.decode("Balance", input, accounts)?,
+				})),
+				("BalanceLock", [_]) => Ok(json!({
+					"id": self.decode("[u8;8]", input, accounts)?,
+					"amount": self.decode("Balance", input, accounts)?,
+					"reasons": variant(input, &["Fee", "Misc", "All"])?,
+				})),
+				("ReserveData", [_, _]) => Ok(json!({
+					"id": self.decode("[u8;8]", input, accounts)?,
+					"amount": self.decode("Balance", input, accounts)?,
+				})),
+				_ => Err(format!("Unknown type `{}`", ty)),
+			}
+		}
+		if let Some(types) = tuple(ty) {
+			return types.iter().map(|ty| self.decode(ty, input, accounts)).collect()
+		}
+		if let Some(len) = ty.strip_prefix("[u8;").and_then(|len| len.strip_suffix(']')) {
+			let len = len.parse::<usize>().map_err(|_| format!("Unknown type `{}`", ty))?;
+			let mut bytes = vec![0; len];
+			input.read(&mut bytes).map_err(err)?;
+			return Ok(hex(&bytes))
+		}
+
+		match ty {
+			"()" => Ok(Value::Null),
+			"bool" => Ok(bool::decode(input).map_err(err)?.into()),
+			"u8" | "Percent" => Ok(u8::decode(input).map_err(err)?.into()),
+			"u16" => Ok(u16::decode(input).map_err(err)?.into()),
+			"u32" | "BlockNumber" | "Index" | "RefCount" | "Perbill" | "Permill" =>
+				Ok(u32::decode(input).map_err(err)?.into()),
+			"u64" | "Moment" | "Weight" | "Slot" | "SetId" | "AuthorityWeight" =>
+				Ok(u64::decode(input).map_err(err)?.into()),
+			"u128" | "Balance" | "Multiplier" =>
+				Ok(u128::decode(input).map_err(err)?.to_string().into()),
+			"Hash" | "H256" | "BlockHash" | "AuthorityId" | "AuraId" | "GrandpaId" =>
+				self.decode("[u8;32]", input, accounts),
+			"Key" => self.decode("Vec<u8>", input, accounts),
+			"KeyValue" => self.decode("(Key,Key)", input, accounts),
+			"AuthorityList" => self.decode("Vec<(AuthorityId,AuthorityWeight)>", input, accounts),
+			"Text" | "String" => {
+				let bytes = Vec::<u8>::decode(input).map_err(err)?;
+				Ok(String::from_utf8_lossy(&bytes).into())
+			},
+			"AccountId" | "AccountId32" => {
+				let account = AccountId32::decode(input).map_err(err)?;
+				let address = account.to_ss58check();
+				accounts.push(account);
+				Ok(address.into())
+			},
+			"LookupSource" | "Address" | "MultiAddress" => match u8::decode(input).map_err(err)? {
+				0 => Ok(json!({ "id": self.decode("AccountId", input, accounts)? })),
+				1 => Ok(json!({ "index": self.decode("Compact<()>", input, accounts)? })),
+				2 => Ok(json!({ "raw": self.decode("Vec<u8>", input, accounts)? })),
+				3 => Ok(json!({ "address32": self.decode("[u8;32]", input, accounts)? })),
+				4 => Ok(json!({ "address20": self.decode("[u8;20]", input, accounts)? })),
+				b => Err(format!("Invalid `MultiAddress` discriminant {}", b)),
+			},
+			"Call" => Ok(serde_json::to_value(self.call_with_accounts(input, accounts)?)
+				.expect("calls serialize to JSON; qed")),
+			"DispatchInfo" => Ok(json!({
+				"weight": self.decode("Weight", input, accounts)?,
+				"class": variant(input, &["Normal", "Operational", "Mandatory"])?,
+				"paysFee": variant(input, &["Yes", "No"])?,
+			})),
+			"DispatchError" => Ok(serde_json::to_value(self.dispatch_error(input)?)
+				.expect("errors serialize to JSON; qed")),
+			"DispatchResult" => match u8::decode(input).map_err(err)? {
+				0 => Ok(json!({ "ok": null })),
+				1 => Ok(json!({ "err": self.decode("DispatchError", input, accounts)? })),
+				b => Err(format!("Invalid `Result` discriminant {}", b)),
+			},
+			"Status" | "BalanceStatus" => variant(input, &["Free", "Reserved"]),
+			"ChangesTrieConfiguration" => Ok(json!({
+				"digestInterval": self.decode("u32", input, accounts)?,
+				"digestLevels": self.decode("u32", input, accounts)?,
+			})),
+			"LastRuntimeUpgradeInfo" => Ok(json!({
+				"specVersion": self.decode("Compact<u32>", input, accounts)?,
+				"specName": self.decode("Text", input, accounts)?,
+			})),
+			_ => Err(format!("Unknown type `{}`", ty)),
+		}
+	}
+
+	/// Decode a call, the index of its pallet and of the call first, then its arguments.
+	pub fn call(&self, input: &mut &[u8]) -> Result<DecodedCall, String> {
+		self.call_with_accounts(input, &mut Vec::new())
+	}
+
+	/// Decode a call, collecting the accounts of its arguments into `accounts`.
+	pub fn call_with_accounts(
+		&self,
+		input: &mut &[u8],
+		accounts: &mut Vec<AccountId32>,
+	) -> Result<DecodedCall, String> {
+		let (pallet_index, call_index) = <(u8, u8)>::decode(input).map_err(err)?;
+		let pallet = self
+			.metadata
+			.pallet_by_index(pallet_index)
+			.ok_or_else(|| format!("Unknown pallet #{}", pallet_index))?;
+		let call = pallet
+			.calls
+			.get(call_index as usize)
+			.ok_or_else(|| format!("Unknown call #{} of {}", call_index, pallet.name))?;
+
+		let mut args = Map::new();
+		for (name, ty) in &call.arguments {
+			let value = self
+				.decode(ty, input, accounts)
+				.map_err(|e| format!("{}::{}: {}: {}", pallet.name, call.name, name, e))?;
+			args.insert(name.clone(), value);
+		}
+		Ok(DecodedCall { pallet: pallet.name.clone(), name: call.name.clone(), args })
+	}
+
+	/// Decode an extrinsic as encoded in a block, collecting its signer and the accounts of its
+	/// call into `accounts`.
+	pub fn extrinsic(
+		&self,
+		input: &mut &[u8],
+		accounts: &mut Vec<AccountId32>,
+	) -> Result<DecodedExtrinsic, String> {
+		let len = Compact::<u32>::decode(input).map_err(err)?.0;
+		if input.len() != len as usize {
+			return Err(format!("Expected {} bytes, got {}", len, input.len()))
+		}
+		let version = u8::decode(input).map_err(err)?;
+		if version & 0b0111_1111 != self.metadata.extrinsic_version {
+			return Err(format!("Unsupported extrinsic version {}", version & 0b0111_1111))
+		}
+
+		let (mut signer, mut nonce, mut tip) = (None, None, None);
+		if version & 0b1000_0000 != 0 {
+			signer = Some(match self.decode("LookupSource", input, accounts)? {
+				Value::Object(address) => match address.get("id") {
+					Some(Value::String(id)) => id.clone(),
+					_ => Value::Object(address).to_string(),
+				},
+				address => address.to_string(),
+			});
+			MultiSignature::decode(input).map_err(err)?;
+			for extension in &self.metadata.signed_extensions {
+				match extension.as_str() {
+					"CheckMortality" | "CheckEra" => {
+						Era::decode(input).map_err(err)?;
+					},
+					"CheckNonce" => nonce = Some(Compact::<u32>::decode(input).map_err(err)?.0),
+					"ChargeTransactionPayment" =>
+						tip = Some(Compact::<u128>::decode(input).map_err(err)?.0.to_string()),
+					"CheckSpecVersion" | "CheckTxVersion" | "CheckGenesis" | "CheckWeight" => (),
+					extension => return Err(format!("Unknown signed extension `{}`", extension)),
+				}
+			}
+		}
+		let call = self.call_with_accounts(input, accounts)?;
+		Ok(DecodedExtrinsic { signer, nonce, tip, call })
+	}
+
+	/// Decode the `EventRecord`s of `System::Events`, collecting the accounts of each event.
+	pub fn events(
+		&self,
+		input: &mut &[u8],
+	) -> Result<Vec<(DecodedEvent, Vec<AccountId32>)>, String> {
+		let len = Compact::<u32>::decode(input).map_err(err)?.0;
+		(0..len).map(|_| self.event_record(input)).collect()
+	}
+
+	fn event_record(&self, input: &mut &[u8]) -> Result<(DecodedEvent, Vec<AccountId32>), String> {
+		let extrinsic = match u8::decode(input).map_err(err)? {
+			// `Phase::ApplyExtrinsic`
+			0 => Some(u32::decode(input).map_err(err)?),
+			// `Phase::Finalization` and `Phase::Initialization`
+			1 | 2 => None,
+			b => return Err(format!("Invalid `Phase` discriminant {}", b)),
+		};
+
+		let (pallet_index, event_index) = <(u8, u8)>::decode(input).map_err(err)?;
+		let pallet = self
+			.metadata
+			.pallet_by_index(pallet_index)
+			.ok_or_else(|| format!("Unknown pallet #{}", pallet_index))?;
+		let event = pallet
+			.events
+			.get(event_index as usize)
+			.ok_or_else(|| format!("Unknown event #{} of {}", event_index, pallet.name))?;
+
+		let mut accounts = Vec::new();
+		let mut error = None;
+		let mut args = Vec::with_capacity(event.arguments.len());
+		for ty in &event.arguments {
+			if normalize(ty) == "DispatchError" {
+				let decoded = self.dispatch_error(input)?;
+				args.push(serde_json::to_value(&decoded).expect("errors serialize to JSON; qed"));
+				error = Some(decoded);
+				continue
+			}
+			let before = *input;
+			let value = self
+				.decode(ty, input, &mut accounts)
+				.map_err(|e| format!("{}::{}: {}", pallet.name, event.name, e))?;
+			// The error of `Sudo::Sudid` and friends is the one of the dispatch they report.
+			if normalize(ty) == "DispatchResult" && before.first() == Some(&1) {
+				error = self.dispatch_error(&mut &before[1..]).ok();
+			}
+			args.push(value);
+		}
+		// Topics are not decoded.
+		Vec::<sp_core::H256>::decode(input).map_err(err)?;
+
+		let event = DecodedEvent {
+			extrinsic,
+			pallet: pallet.name.clone(),
+			name: event.name.clone(),
+			args,
+			error,
+		};
+		Ok((event, accounts))
+	}
+
+	fn dispatch_error(&self, input: &mut &[u8]) -> Result<DecodedError, String> {
+		let error = DispatchError::decode(input).map_err(err)?;
+		Ok(DecodedError::new(&error, self.metadata))
+	}
+}
+
+/// Strip the paths and the associated type syntax of a type name, e.g.
+/// `<T::Lookup as StaticLookup>::Source` becomes `LookupSource`.
+pub fn normalize(ty: &str) -> String {
+	let mut ty: String = ty.chars().filter(|c| !c.is_whitespace()).collect();
+	for (from, to) in &[
+		("<T::LookupasStaticLookup>::Source", "LookupSource"),
+		("BalanceOf<T>", "Balance"),
+		("BalanceOf<T,I>", "Balance"),
+	] {
+		ty = ty.replace(from, to);
+	}
+	// `<T as Config>::Call` and `<T as frame_system::Config>::AccountId`
+	while let Some(start) = ty.find("<Tas") {
+		match ty[start..].find(">::") {
+			Some(end) => ty.replace_range(start..start + end + 3, ""),
+			None => break,
+		}
+	}
+	ty.replace("T::", "")
+}
+
+/// Split `Name<A, B>` into `Name` and its arguments.
+pub fn generic(ty: &str) -> Option<(&str, Vec<&str>)> {
+	let start = ty.find('<')?;
+	let inner = ty.strip_suffix('>')?.get(start + 1..)?;
+	let mut name = &ty[..start];
+	if name == "BoundedVec" || name == "WeakBoundedVec" {
+		name = "Vec";
+	}
+	let mut args = split(inner);
+	if name == "Vec" {
+		// The bound of a `BoundedVec`.
+		args.truncate(1);
+	}
+	Some((name, args))
+}
+
+/// Split `(A, B)` into its types.
+pub fn tuple(ty: &str) -> Option<Vec<&str>> {
+	let inner = ty.strip_prefix('(')?.strip_suffix(')')?;
+	if inner.is_empty() {
+		return None
+	}
+	Some(split(inner))
+}
+
+/// Split a comma separated list of types, ignoring the commas of nested types.
+fn split(list: &str) -> Vec<&str> {
+	let mut types = Vec::new();
+	let (mut depth, mut start) = (0, 0);
+	for (i, c) in list.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth -= 1,
+			',' if depth == 0 => {
+				types.push(&list[start..i]);
+				start = i + 1;
+			},
+			_ => (),
+		}
+	}
+	types.push(&list[start..]);
+	types
+}
+
+/// Decode the discriminant of a fieldless enum.
+fn variant(input: &mut &[u8], names: &[&str]) -> Result<Value, String> {
+	let index = u8::decode(input).map_err(err)?;
+	names
+		.get(index as usize)
+		.map(|name| Value::from(*name))
+		.ok_or_else(|| format!("Invalid discriminant {} of {:?}", index, names))
+}
+
+/// Whether values of `ty` are rendered as decimal strings.
+fn is_u128(ty: &str) -> bool {
+	matches!(ty, "u128" | "Balance" | "Multiplier")
+}
+
+fn hex(bytes: &[u8]) -> Value {
+	serde_json::to_value(Bytes(bytes.to_vec())).expect("bytes serialize to JSON; qed")
+}
+
+fn err(e: codec::Error) -> String {
+	e.to_string()
+}
+
+/// Storage key of `System::Events`, whose value [`Decoder::events`] decodes.
+pub fn events_key() -> Vec<u8> {
+	[twox_128(b"System"), twox_128(b"Events")].concat()
+}
@@ -0,0 +1,100 @@
+//! Human-readable dispatch errors.
+//!
+//! A `DispatchError::Module` only carries the index of the pallet and of the error in it. Their
+//! names and documentation are resolved with the metadata of the runtime that returned the
+//! error, so that they stay right across runtime upgrades.
+
+use crate::metadata::Metadata;
+use node_template_runtime::Event;
+use serde::{Deserialize, Serialize};
+use sp_runtime::DispatchError;
+use std::fmt;
+
+/// A dispatch error, with the names of the metadata.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedError {
+	/// Pallet of a module error, `None` for the other errors.
+	pub pallet: Option<String>,
+	/// Name of the error, e.g. `NoneValue` or `BadOrigin`.
+	pub name: String,
+	/// Doc comment of a module error, or description of the other errors.
+	pub documentation: String,
+}
+
+impl DecodedError {
+	/// Decode `error` with `metadata`.
+	pub fn new(error: &DispatchError, metadata: &Metadata) -> Self {
+		match *error {
+			DispatchError::Module { index, error, .. } => Self::module(index, error, metadata),
+			DispatchError::Other(_) => Self {
+				pallet: None,
+				name: "Other".into(),
+				documentation: <&str>::from(*error).into(),
+			},
+			_ => Self {
+				pallet: None,
+				name: format!("{:?}", error),
+				documentation: <&str>::from(*error).into(),
+			},
+		}
+	}
+
+	/// Decode the error with index `error` of the pallet with index `pallet` with `metadata`.
+	pub fn module(pallet: u8, error: u8, metadata: &Metadata) -> Self {
+		let pallet_metadata = metadata.pallet_by_index(pallet);
+		let error_metadata = pallet_metadata.and_then(|pallet| pallet.errors.get(error as usize));
+
+		match (pallet_metadata, error_metadata) {
+			(Some(pallet), Some(error)) => Self {
+				pallet: Some(pallet.name.clone()),
+				name: error.name.clone(),
+				documentation: documentation(&error.documentation),
+			},
+			(Some(pallet), None) => Self {
+				pallet: Some(pallet.name.clone()),
+				name: format!("Error #{}", error),
+				documentation: "Unknown error of the pallet".into(),
+			},
+			(None, _) => Self {
+				pallet: None,
+				name: format!("Error #{} of pallet #{}", error, pallet),
+				documentation: "Unknown pallet".into(),
+			},
+		}
+	}
+}
+
+impl fmt::Display for DecodedError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if let Some(pallet) = &self.pallet {
+			write!(f, "{}::", pallet)?;
+		}
+		write!(f, "{}", self.name)?;
+		if !self.documentation.is_empty() {
+			write!(f, " ({})", self.documentation)?;
+		}
+		Ok(())
+	}
+}
+
+/// The dispatch error reported by `event`, if any.
+///
+/// These are the failure of an extrinsic, and of the calls dispatched by `Sudo`.
+pub fn event_error(event: &Event) -> Option<&DispatchError> {
+	match event {
+		Event::System(frame_system::Event::ExtrinsicFailed(error, _)) |
+		Event::Sudo(pallet_sudo::Event::Sudid(Err(error))) |
+		Event::Sudo(pallet_sudo::Event::SudoAsDone(Err(error))) => Some(error),
+		_ => None,
+	}
+}
+
+/// Join the lines of a doc comment.
+fn documentation(lines: &[String]) -> String {
+	lines
+		.iter()
+		.map(|line| line.trim())
+		.filter(|line| !line.is_empty())
+		.collect::<Vec<_>>()
+		.join(" ")
+}
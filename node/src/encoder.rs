@@ -0,0 +1,259 @@
+//! Encoding of JSON values into SCALE, the counterpart of [`crate::decoder`].
+//!
+//! Values are encoded by the type names of the metadata, and accepted in the form the decoder
+//! renders them. Numbers may also be given as decimal strings, accounts as `0x` prefixed hex and
+//! addresses as a bare account.
+
+use crate::{
+	decoder::{generic, normalize, tuple},
+	metadata::Metadata,
+};
+use codec::{Compact, Encode};
+use serde_json::Value;
+use sp_core::{crypto::Ss58Codec, Bytes};
+use sp_runtime::AccountId32;
+use std::convert::{TryFrom, TryInto};
+
+/// Encodes values with the type names of a [`Metadata`].
+pub struct Encoder<'a> {
+	metadata: &'a Metadata,
+}
+
+impl<'a> Encoder<'a> {
+	/// Create an encoder for the runtime described by `metadata`.
+	pub fn new(metadata: &'a Metadata) -> Self {
+		Self { metadata }
+	}
+
+	/// Encode `value` as a value of type `ty`.
+	pub fn encode(&self, ty: &str, value: &Value, out: &mut Vec<u8>) -> Result<(), String> {
+		let ty = normalize(ty);
+		let ty = ty.as_str();
+
+		if let Some((name, args)) = generic(ty) {
+			return match (name, args.as_slice()) {
+				("Vec", ["u8"]) => {
+					bytes(value)?.encode_to(out);
+					Ok(())
+				},
+				("Vec", [inner]) => {
+					let values = array(value)?;
+					Compact(values.len() as u32).encode_to(out);
+					values.iter().try_for_each(|value| self.encode(inner, value, out))
+				},
+				("Option", [inner]) => match value {
+					Value::Null => {
+						out.push(0);
+						Ok(())
+					},
+					value => {
+						out.push(1);
+						self.encode(inner, value, out)
+					},
+				},
+				("Box", [inner]) => self.encode(inner, value, out),
+				("Compact", ["()"]) => Ok(()),
+				("Compact", [_]) => {
+					Compact(number(value)?).encode_to(out);
+					Ok(())
+				},
+				_ => Err(format!("Unsupported type `{}`", ty)),
+			}
+		}
+		if let Some(types) = tuple(ty) {
+			let values = array(value)?;
+			if values.len() != types.len() {
+				return Err(format!("Expected {} values, got {}", types.len(), values.len()))
+			}
+			return types.iter().zip(values).try_for_each(|(ty, value)| self.encode(ty, value, out))
+		}
+		if let Some(len) = ty.strip_prefix("[u8;").and_then(|len| len.strip_suffix(']')) {
+			let len = len.parse::<usize>().map_err(|_| format!("Unsupported type `{}`", ty))?;
+			let bytes = bytes(value)?;
+			if bytes.len() != len {
+				return Err(format!("Expected {} bytes, got {}", len, bytes.len()))
+			}
+			out.extend(bytes);
+			return Ok(())
+		}
+
+		match ty {
+			"()" => Ok(()),
+			"bool" => match value {
+				Value::Bool(b) => {
+					b.encode_to(out);
+					Ok(())
+				},
+				_ => Err(format!("Expected a boolean, got {}", value)),
+			},
+			"u8" | "Percent" => {
+				sized::<u8>(value)?.encode_to(out);
+				Ok(())
+			},
+			"u16" => {
+				sized::<u16>(value)?.encode_to(out);
+				Ok(())
+			},
+			"u32" | "BlockNumber" | "Index" | "RefCount" | "Perbill" | "Permill" => {
+				sized::<u32>(value)?.encode_to(out);
+				Ok(())
+			},
+			"u64" | "Moment" | "Weight" | "Slot" | "SetId" | "AuthorityWeight" => {
+				sized::<u64>(value)?.encode_to(out);
+				Ok(())
+			},
+			"u128" | "Balance" | "Multiplier" => {
+				number(value)?.encode_to(out);
+				Ok(())
+			},
+			"Hash" | "H256" | "BlockHash" | "AuthorityId" | "AuraId" | "GrandpaId" =>
+				self.encode("[u8;32]", value, out),
+			"Key" => self.encode("Vec<u8>", value, out),
+			"KeyValue" => self.encode("(Key,Key)", value, out),
+			"AuthorityList" => self.encode("Vec<(AuthorityId,AuthorityWeight)>", value, out),
+			"Text" | "String" => match value {
+				Value::String(s) => {
+					s.encode_to(out);
+					Ok(())
+				},
+				_ => Err(format!("Expected a string, got {}", value)),
+			},
+			"AccountId" | "AccountId32" => {
+				account(value)?.encode_to(out);
+				Ok(())
+			},
+			"LookupSource" | "Address" | "MultiAddress" => match value {
+				Value::Object(fields) if fields.len() == 1 => {
+					let (kind, value) = fields.iter().next().expect("one field; qed");
+					let (index, ty) = match kind.as_str() {
+						"id" => (0, "AccountId"),
+						"index" => (1, "Compact<()>"),
+						"raw" => (2, "Vec<u8>"),
+						"address32" => (3, "[u8;32]"),
+						"address20" => (4, "[u8;20]"),
+						_ => return Err(format!("Unknown `MultiAddress` variant `{}`", kind)),
+					};
+					out.push(index);
+					self.encode(ty, value, out)
+				},
+				value => {
+					out.push(0);
+					self.encode("AccountId", value, out)
+				},
+			},
+			"Call" => match value {
+				Value::Object(fields) => {
+					let field = |name: &str| {
+						fields
+							.get(name)
+							.and_then(Value::as_str)
+							.ok_or_else(|| format!("Missing `{}` of the call", name))
+					};
+					let args = fields.get("args").unwrap_or(&Value::Null);
+					out.extend(self.call(field("pallet")?, field("name")?, args)?);
+					Ok(())
+				},
+				_ => Err(format!("Expected a call, got {}", value)),
+			},
+			_ => Err(format!("Unsupported type `{}`", ty)),
+		}
+	}
+
+	/// Encode the call `name` of `pallet` with `args`, either in order or by name.
+	pub fn call(&self, pallet: &str, name: &str, args: &Value) -> Result<Vec<u8>, String> {
+		let pallet_metadata = self
+			.metadata
+			.pallet(pallet)
+			.ok_or_else(|| format!("Unknown pallet `{}`", pallet))?;
+		let (index, call) = pallet_metadata
+			.calls
+			.iter()
+			.enumerate()
+			.find(|(_, call)| call.name == name)
+			.ok_or_else(|| format!("Unknown call `{}` of {}", name, pallet))?;
+
+		let values = match args {
+			Value::Null => Vec::new(),
+			Value::Array(values) => values.iter().collect(),
+			Value::Object(values) => call
+				.arguments
+				.iter()
+				.map(|(arg, _)| {
+					values
+						.get(arg)
+						.ok_or_else(|| format!("{}::{}: missing `{}`", pallet, name, arg))
+				})
+				.collect::<Result<_, _>>()?,
+			_ =>
+				return Err(format!("{}::{}: arguments must be an array or an object", pallet, name)),
+		};
+		if values.len() != call.arguments.len() {
+			return Err(format!(
+				"{}::{} takes {} argument(s), got {}",
+				pallet,
+				name,
+				call.arguments.len(),
+				values.len()
+			))
+		}
+
+		let mut out = vec![pallet_metadata.index, index as u8];
+		for ((arg, ty), value) in call.arguments.iter().zip(values) {
+			self.encode(ty, value, &mut out)
+				.map_err(|e| format!("{}::{}: {}: {}", pallet, name, arg, e))?;
+		}
+		Ok(out)
+	}
+}
+
+/// Parse a command line argument: JSON if it is valid JSON, a string otherwise.
+///
+/// Numbers too large for a JSON number are kept as strings, so that balances keep their precision.
+pub fn parse_arg(arg: &str) -> Value {
+	match serde_json::from_str(arg) {
+		Ok(Value::Number(n)) if n.as_u64().is_none() => arg.into(),
+		Ok(value) => value,
+		Err(_) => arg.into(),
+	}
+}
+
+fn array(value: &Value) -> Result<&Vec<Value>, String> {
+	value.as_array().ok_or_else(|| format!("Expected an array, got {}", value))
+}
+
+/// A number, given as a JSON number or as a decimal string.
+fn number(value: &Value) -> Result<u128, String> {
+	match value {
+		Value::Number(n) => n.as_u64().map(u128::from),
+		Value::String(s) => s.parse().ok(),
+		_ => None,
+	}
+	.ok_or_else(|| format!("Expected an unsigned integer, got {}", value))
+}
+
+fn sized<T: TryFrom<u128>>(value: &Value) -> Result<T, String> {
+	number(value)?.try_into().map_err(|_| format!("{} is out of range", value))
+}
+
+/// Bytes given as `0x` prefixed hex, or as a string of which the UTF-8 bytes are taken.
+fn bytes(value: &Value) -> Result<Vec<u8>, String> {
+	match value {
+		Value::String(s) if s.starts_with("0x") => serde_json::from_value::<Bytes>(value.clone())
+			.map(|bytes| bytes.0)
+			.map_err(|e| e.to_string()),
+		Value::String(s) => Ok(s.as_bytes().to_vec()),
+		_ => Err(format!("Expected bytes, got {}", value)),
+	}
+}
+
+/// An account given as an SS58 address or as `0x` prefixed hex.
+fn account(value: &Value) -> Result<AccountId32, String> {
+	match value {
+		Value::String(s) if s.starts_with("0x") => <[u8; 32]>::try_from(bytes(value)?.as_slice())
+			.map(Into::into)
+			.map_err(|_| format!("Expected 32 bytes, got {}", value)),
+		Value::String(s) =>
+			AccountId32::from_ss58check(s).map_err(|e| format!("Invalid address {}: {:?}", s, e)),
+		_ => Err(format!("Expected an account, got {}", value)),
+	}
+}
@@ -0,0 +1,147 @@
+//! HTTP endpoints for the probes of orchestrators such as Kubernetes.
+//!
+//! - `/health` answers as long as the process is up.
+//! - `/ready` answers once the node has peers, is not major syncing and neither its best nor its
+//!   finalized block lag behind.
+//! - `/authoring` answers if a key of the local keystore is in the current Aura authority set.
+//!
+//! Every endpoint answers `200 OK` or `503 Service Unavailable`, with the reason in the body.
+
+use hyper::{
+	service::{make_service_fn, service_fn},
+	Body, Response, Server, StatusCode,
+};
+use node_template_runtime::{opaque::Block, BlockNumber, Hash};
+use sc_network::{NetworkService, SyncState};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::{sr25519::AuthorityId as AuraId, AuraApi};
+use sp_core::crypto::{key_types::AURA, Public, Ss58Codec};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::generic::BlockId;
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+/// Configuration of the health server.
+#[derive(Clone, Copy, Debug)]
+pub struct HealthConfig {
+	/// Address to listen on.
+	pub addr: SocketAddr,
+	/// Number of blocks the best block may be behind the best block seen on the network.
+	pub max_sync_lag: BlockNumber,
+	/// Number of blocks the finalized block may be behind the best block.
+	pub max_finality_lag: BlockNumber,
+}
+
+/// The probes answered by the health server.
+pub struct Probes<C> {
+	/// Configuration of the server.
+	pub config: HealthConfig,
+	/// Whether the node is expected to have peers, i.e. it is not a development chain.
+	pub require_peers: bool,
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// The network of the node.
+	pub network: Arc<NetworkService<Block, Hash>>,
+	/// The keystore holding the authoring keys.
+	pub keystore: SyncCryptoStorePtr,
+}
+
+impl<C> Probes<C>
+where
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block> + Send + Sync + 'static,
+	C::Api: AuraApi<Block, AuraId>,
+{
+	/// Serve the probes on `listener` until the node stops.
+	pub async fn run(self, listener: std::net::TcpListener) {
+		let server = match Server::from_tcp(listener) {
+			Ok(server) => server,
+			Err(e) => {
+				log::error!("Failed to start the health server: {}", e);
+				return
+			},
+		};
+
+		let probes = Arc::new(self);
+		let service = make_service_fn(move |_| {
+			let probes = probes.clone();
+			async move {
+				Ok::<_, Infallible>(service_fn(move |request| {
+					let probes = probes.clone();
+					async move { Ok::<_, Infallible>(probes.respond(request.uri().path()).await) }
+				}))
+			}
+		});
+
+		if let Err(e) = server.serve(service).await {
+			log::error!("Health server failed: {}", e);
+		}
+	}
+
+	async fn respond(&self, path: &str) -> Response<Body> {
+		let result = match path {
+			"/health" => Ok("OK".into()),
+			"/ready" => self.ready().await,
+			"/authoring" => self.authoring(),
+			_ =>
+				return Response::builder()
+					.status(StatusCode::NOT_FOUND)
+					.body(Body::from("Not found"))
+					.expect("response is valid; qed"),
+		};
+
+		let (status, body) = match result {
+			Ok(body) => (StatusCode::OK, body),
+			Err(body) => (StatusCode::SERVICE_UNAVAILABLE, body),
+		};
+		Response::builder()
+			.status(status)
+			.body(Body::from(body))
+			.expect("response is valid; qed")
+	}
+
+	async fn ready(&self) -> Result<String, String> {
+		let status =
+			self.network.status().await.map_err(|_| "Network is not running".to_string())?;
+		if self.require_peers && status.num_connected_peers == 0 {
+			return Err("No peers".into())
+		}
+		if let SyncState::Downloading = status.sync_state {
+			return Err("Major syncing".into())
+		}
+
+		let info = self.client.info();
+		let sync_lag =
+			status.best_seen_block.map_or(0, |seen| seen.saturating_sub(info.best_number));
+		if sync_lag > self.config.max_sync_lag {
+			return Err(format!("Best block is {} blocks behind the network", sync_lag))
+		}
+		let finality_lag = info.best_number - info.finalized_number;
+		if finality_lag > self.config.max_finality_lag {
+			return Err(format!("Finalized block is {} blocks behind the best block", finality_lag))
+		}
+
+		Ok(format!(
+			"peers: {}, best: {}, finalized: {}",
+			status.num_connected_peers, info.best_number, info.finalized_number
+		))
+	}
+
+	fn authoring(&self) -> Result<String, String> {
+		let best = BlockId::Hash(self.client.info().best_hash);
+		let authorities = self
+			.client
+			.runtime_api()
+			.authorities(&best)
+			.map_err(|e| format!("Unable to read the authorities: {:?}", e))?;
+
+		authorities
+			.into_iter()
+			.find(|authority| {
+				SyncCryptoStore::has_keys(&*self.keystore, &[(authority.to_raw_vec(), AURA)])
+			})
+			.map(|authority| {
+				format!("Authoring as {}", sp_core::sr25519::Public::from(authority).to_ss58check())
+			})
+			.ok_or_else(|| "No local key is in the authority set".into())
+	}
+}
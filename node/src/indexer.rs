@@ -0,0 +1,526 @@
+//! Index of the extrinsics and events of the best chain, stored in SQLite.
+//!
+//! The indexer follows the imported blocks and decodes their extrinsics and events with the
+//! metadata of the runtime that produced them. Only the best chain is indexed: when a reorg
+//! happens, the blocks of the retracted branch are removed before the enacted ones are indexed.
+//! Blocks are flagged once finalized.
+//!
+//! Events are read from the state, so blocks whose state was pruned are indexed without their
+//! events. Run the node with `--pruning archive` to index the whole history.
+
+use crate::{
+	decoder::{events_key, DecodedCall, DecodedEvent, DecodedExtrinsic, Decoder},
+	dispatch_error::DecodedError,
+	metadata::MetadataCache,
+};
+use codec::Encode;
+use futures::{future::Either, stream, StreamExt};
+use node_template_runtime::{opaque::Block, BlockNumber, Hash};
+use rusqlite::{params, Connection, OptionalExtension, ToSql};
+use sc_client_api::{
+	backend::{Backend, StateBackend},
+	BlockBackend, BlockchainEvents,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_api::{Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::crypto::Ss58Codec;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as _},
+	AccountId32,
+};
+use std::{
+	path::Path,
+	sync::{Arc, Mutex},
+};
+
+type Header = <Block as BlockT>::Header;
+
+/// Maximum number of rows returned by a query.
+pub const MAX_LIMIT: u32 = 1000;
+
+/// Number of blocks indexed per database transaction.
+const BATCH_SIZE: BlockNumber = 1000;
+
+const SCHEMA: &str = "
+	PRAGMA journal_mode = WAL;
+	CREATE TABLE IF NOT EXISTS blocks (
+		number INTEGER PRIMARY KEY,
+		hash BLOB NOT NULL,
+		parent_hash BLOB NOT NULL,
+		finalized INTEGER NOT NULL DEFAULT 0
+	);
+	CREATE TABLE IF NOT EXISTS extrinsics (
+		block_number INTEGER NOT NULL,
+		idx INTEGER NOT NULL,
+		hash BLOB NOT NULL,
+		pallet TEXT NOT NULL,
+		name TEXT NOT NULL,
+		args TEXT NOT NULL,
+		signer TEXT,
+		nonce INTEGER,
+		tip TEXT,
+		success INTEGER NOT NULL,
+		error TEXT,
+		PRIMARY KEY (block_number, idx)
+	);
+	CREATE TABLE IF NOT EXISTS events (
+		block_number INTEGER NOT NULL,
+		idx INTEGER NOT NULL,
+		extrinsic_idx INTEGER,
+		pallet TEXT NOT NULL,
+		name TEXT NOT NULL,
+		args TEXT NOT NULL,
+		error TEXT,
+		PRIMARY KEY (block_number, idx)
+	);
+	-- Accounts signing or mentioned in the arguments of an extrinsic, or mentioned by an event.
+	CREATE TABLE IF NOT EXISTS accounts (
+		account TEXT NOT NULL,
+		block_number INTEGER NOT NULL,
+		extrinsic_idx INTEGER,
+		event_idx INTEGER
+	);
+	CREATE INDEX IF NOT EXISTS extrinsics_call ON extrinsics (pallet, name, block_number);
+	CREATE INDEX IF NOT EXISTS events_event ON events (pallet, name, block_number);
+	CREATE INDEX IF NOT EXISTS accounts_account ON accounts (account, block_number);
+";
+
+/// Follows the best chain and writes it to the database.
+pub struct Indexer<C, B> {
+	client: Arc<C>,
+	backend: Arc<B>,
+	db: Connection,
+	metadata: MetadataCache,
+}
+
+impl<C, B> Indexer<C, B>
+where
+	B: Backend<Block>,
+	C: BlockchainEvents<Block> + BlockBackend<Block> + HeaderBackend<Block>,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: MetadataApi<Block>,
+{
+	/// Open the database at `path`, creating it if needed.
+	pub fn open(path: &Path, client: Arc<C>, backend: Arc<B>) -> Result<Self, String> {
+		let db = Connection::open(path).map_err(db_error)?;
+		db.execute_batch(SCHEMA).map_err(db_error)?;
+		Ok(Self { client, backend, db, metadata: Default::default() })
+	}
+
+	/// Index the blocks imported since the last run, then follow the node until it stops.
+	pub async fn run(mut self) {
+		let info = self.client.info();
+		if let Err(e) =
+			self.index(info.best_number).and_then(|_| self.finalize(info.finalized_number))
+		{
+			log::error!("Indexer failed to catch up: {}", e);
+		}
+
+		let imports = self.client.import_notification_stream().map(Either::Left);
+		let finality = self.client.finality_notification_stream().map(Either::Right);
+		let mut notifications = stream::select(imports, finality);
+
+		while let Some(notification) = notifications.next().await {
+			let result = match notification {
+				Either::Left(import) if import.is_new_best => self.index(*import.header.number()),
+				Either::Left(_) => Ok(()),
+				Either::Right(finality) => self.finalize(*finality.header.number()),
+			};
+			if let Err(e) = result {
+				log::error!("Indexer failed: {}", e);
+			}
+		}
+	}
+
+	/// Make block `best` of the best chain the head of the indexed chain.
+	///
+	/// The best chain is indexed forward from the last block it has in common with the index, and
+	/// committed every [`BATCH_SIZE`] blocks: catching up on a long chain neither holds it in
+	/// memory nor writes it in a single transaction.
+	fn index(&mut self, best: BlockNumber) -> Result<(), String> {
+		// Walk back from the indexed head over the blocks the best chain retracted.
+		let mut from = indexed_head(&self.db)?.map_or(0, |head| head.min(best) + 1);
+		while from > 0 {
+			let hash = self.client.hash(from - 1).map_err(|e| e.to_string())?;
+			if indexed_hash(&self.db, from - 1)? == hash {
+				break
+			}
+			from -= 1;
+		}
+
+		let mut tx = self.db.unchecked_transaction().map_err(db_error)?;
+		for table in &["blocks", "extrinsics", "events", "accounts"] {
+			let column = if *table == "blocks" { "number" } else { "block_number" };
+			tx.execute(&format!("DELETE FROM {} WHERE {} >= ?1", table, column), params![from])
+				.map_err(db_error)?;
+		}
+		let mut parent = if from == 0 { None } else { indexed_hash(&tx, from - 1)? };
+		for number in from..=best {
+			let header = self
+				.client
+				.header(BlockId::Number(number))
+				.map_err(|e| e.to_string())?
+				.ok_or_else(|| format!("Unknown block #{}", number))?;
+			// The batches committed so far stay a prefix of the chain if it changes meanwhile, the
+			// next import notification indexes the new one.
+			if number > 0 && parent != Some(*header.parent_hash()) {
+				return Err(format!("The best chain changed while indexing block #{}", number))
+			}
+			parent = Some(header.hash());
+			index_block(&tx, &*self.client, &*self.backend, &mut self.metadata, &header)?;
+			if (number - from + 1) % BATCH_SIZE == 0 {
+				tx.commit().map_err(db_error)?;
+				tx = self.db.unchecked_transaction().map_err(db_error)?;
+			}
+		}
+		tx.commit().map_err(db_error)
+	}
+
+	/// Flag the indexed blocks up to `number` as finalized.
+	fn finalize(&self, number: BlockNumber) -> Result<(), String> {
+		self.db
+			.execute(
+				"UPDATE blocks SET finalized = 1 WHERE number <= ?1 AND finalized = 0",
+				params![number],
+			)
+			.map(|_| ())
+			.map_err(db_error)
+	}
+}
+
+fn index_block<C, B>(
+	db: &Connection,
+	client: &C,
+	backend: &B,
+	metadata: &mut MetadataCache,
+	header: &Header,
+) -> Result<(), String>
+where
+	B: Backend<Block>,
+	C: BlockBackend<Block> + ProvideRuntimeApi<Block>,
+	C::Api: MetadataApi<Block>,
+{
+	let number = *header.number();
+	let hash = header.hash();
+	db.execute(
+		"INSERT INTO blocks (number, hash, parent_hash) VALUES (?1, ?2, ?3)",
+		params![number, hash.as_bytes(), header.parent_hash().as_bytes()],
+	)
+	.map_err(db_error)?;
+	if number == 0 {
+		return Ok(())
+	}
+
+	// The block was built by the runtime of its parent.
+	let decoder = Decoder::new(metadata.get(client, *header.parent_hash())?);
+
+	let events = match backend.state_at(BlockId::Hash(hash)).ok().map(|s| s.storage(&events_key()))
+	{
+		Some(Ok(events)) => events.unwrap_or_default(),
+		_ => {
+			log::debug!("Indexing block #{} without events, its state is not available", number);
+			Vec::new()
+		},
+	};
+	let events = decoder.events(&mut &events[..]).unwrap_or_else(|e| {
+		log::warn!("Unable to decode the events of block #{}: {}", number, e);
+		Vec::new()
+	});
+	for (index, (event, accounts)) in events.iter().enumerate() {
+		db.execute(
+			"INSERT INTO events (block_number, idx, extrinsic_idx, pallet, name, args, error)
+			VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+			params![
+				number,
+				index as u32,
+				event.extrinsic,
+				event.pallet,
+				event.name,
+				Value::from(event.args.clone()).to_string(),
+				event.error.as_ref().map(to_json),
+			],
+		)
+		.map_err(db_error)?;
+		for account in accounts {
+			db.execute(
+				"INSERT INTO accounts (account, block_number, event_idx) VALUES (?1, ?2, ?3)",
+				params![account.to_ss58check(), number, index as u32],
+			)
+			.map_err(db_error)?;
+		}
+	}
+
+	let body = client
+		.block_body(&BlockId::Hash(hash))
+		.map_err(|e| e.to_string())?
+		.unwrap_or_default();
+	for (index, xt) in body.iter().enumerate() {
+		let encoded = xt.encode();
+		let mut accounts = Vec::<AccountId32>::new();
+		let xt = decoder.extrinsic(&mut &encoded[..], &mut accounts).unwrap_or_else(|e| {
+			log::warn!("Unable to decode extrinsic #{} of block #{}: {}", index, number, e);
+			let call = DecodedCall {
+				pallet: String::new(),
+				name: String::new(),
+				args: Default::default(),
+			};
+			DecodedExtrinsic { signer: None, nonce: None, tip: None, call }
+		});
+		let DecodedExtrinsic { signer, nonce, tip, call } = xt;
+
+		let error = events
+			.iter()
+			.map(|(event, _)| event)
+			.find(|event| {
+				event.extrinsic == Some(index as u32) &&
+					event.pallet == "System" &&
+					event.name == "ExtrinsicFailed"
+			})
+			.and_then(|event| event.error.as_ref());
+		db.execute(
+			"INSERT INTO extrinsics
+			(block_number, idx, hash, pallet, name, args, signer, nonce, tip, success, error)
+			VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+			params![
+				number,
+				index as u32,
+				&sp_core::blake2_256(&encoded)[..],
+				call.pallet,
+				call.name,
+				Value::from(call.args).to_string(),
+				signer,
+				nonce,
+				tip,
+				error.is_none(),
+				error.map(to_json),
+			],
+		)
+		.map_err(db_error)?;
+		for account in accounts {
+			db.execute(
+				"INSERT INTO accounts (account, block_number, extrinsic_idx) VALUES (?1, ?2, ?3)",
+				params![account.to_ss58check(), number, index as u32],
+			)
+			.map_err(db_error)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Number of the last indexed block.
+fn indexed_head(db: &Connection) -> Result<Option<BlockNumber>, String> {
+	db.query_row("SELECT MAX(number) FROM blocks", params![], |row| row.get(0))
+		.map_err(db_error)
+}
+
+/// Hash of the indexed block `number`.
+fn indexed_hash(db: &Connection, number: BlockNumber) -> Result<Option<Hash>, String> {
+	db.query_row("SELECT hash FROM blocks WHERE number = ?1", params![number], |row| {
+		row.get::<_, Vec<u8>>(0)
+	})
+	.optional()
+	.map(|hash| hash.map(|hash| Hash::from_slice(&hash)))
+	.map_err(db_error)
+}
+
+/// Criteria of a query, every given one must match.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Filter {
+	/// SS58 address of an account signing the extrinsic or mentioned in its arguments, or
+	/// mentioned by the event.
+	pub account: Option<String>,
+	/// Pallet of the call or event.
+	pub pallet: Option<String>,
+	/// Name of the call or event.
+	pub name: Option<String>,
+	/// First block of the range.
+	pub from: Option<BlockNumber>,
+	/// Last block of the range.
+	pub to: Option<BlockNumber>,
+	/// Maximum number of results, at most [`MAX_LIMIT`].
+	pub limit: Option<u32>,
+}
+
+/// An indexed extrinsic.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedExtrinsic {
+	/// Number of the block.
+	pub block_number: BlockNumber,
+	/// Hash of the block.
+	pub block_hash: Hash,
+	/// Whether the block is finalized.
+	pub finalized: bool,
+	/// Index of the extrinsic in the block.
+	pub index: u32,
+	/// Hash of the extrinsic.
+	pub hash: Hash,
+	/// SS58 address of the signer, for signed extrinsics.
+	pub signer: Option<String>,
+	/// Nonce of a signed extrinsic.
+	pub nonce: Option<u32>,
+	/// Tip of a signed extrinsic.
+	pub tip: Option<String>,
+	/// The call.
+	pub call: DecodedCall,
+	/// Whether the call succeeded.
+	pub success: bool,
+	/// Why the call failed.
+	pub error: Option<DecodedError>,
+}
+
+/// An indexed event.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedEvent {
+	/// Number of the block.
+	pub block_number: BlockNumber,
+	/// Hash of the block.
+	pub block_hash: Hash,
+	/// Whether the block is finalized.
+	pub finalized: bool,
+	/// Index of the event in the block.
+	pub index: u32,
+	/// The event.
+	#[serde(flatten)]
+	pub event: DecodedEvent,
+}
+
+/// Read access to the database written by an [`Indexer`].
+pub struct Index {
+	db: Mutex<Connection>,
+}
+
+impl Index {
+	/// Open the database at `path`, which must have been created by [`Indexer::open`].
+	pub fn open(path: &Path) -> Result<Self, String> {
+		Ok(Self { db: Mutex::new(Connection::open(path).map_err(db_error)?) })
+	}
+
+	/// Extrinsics matching `filter`, in chain order.
+	pub fn extrinsics(&self, filter: &Filter) -> Result<Vec<IndexedExtrinsic>, String> {
+		let db = self.db.lock().expect("lock is not poisoned; qed");
+		let (conditions, params) = conditions(filter, "x", "extrinsic_idx");
+		let mut statement = db
+			.prepare(&format!(
+				"SELECT x.block_number, b.hash, b.finalized, x.idx, x.hash, x.signer, x.nonce,
+				x.tip, x.pallet, x.name, x.args, x.success, x.error
+				FROM extrinsics x JOIN blocks b ON b.number = x.block_number
+				WHERE {} ORDER BY x.block_number, x.idx LIMIT {}",
+				conditions,
+				limit(filter),
+			))
+			.map_err(db_error)?;
+		let rows = statement
+			.query_map(&*params, |row| {
+				Ok(IndexedExtrinsic {
+					block_number: row.get(0)?,
+					block_hash: Hash::from_slice(&row.get::<_, Vec<u8>>(1)?),
+					finalized: row.get(2)?,
+					index: row.get(3)?,
+					hash: Hash::from_slice(&row.get::<_, Vec<u8>>(4)?),
+					signer: row.get(5)?,
+					nonce: row.get(6)?,
+					tip: row.get(7)?,
+					call: DecodedCall {
+						pallet: row.get(8)?,
+						name: row.get(9)?,
+						args: from_json(row.get(10)?).unwrap_or_default(),
+					},
+					success: row.get(11)?,
+					error: row.get::<_, Option<String>>(12)?.and_then(from_json),
+				})
+			})
+			.map_err(db_error)?;
+		rows.collect::<Result<_, _>>().map_err(db_error)
+	}
+
+	/// Events matching `filter`, in chain order.
+	pub fn events(&self, filter: &Filter) -> Result<Vec<IndexedEvent>, String> {
+		let db = self.db.lock().expect("lock is not poisoned; qed");
+		let (conditions, params) = conditions(filter, "e", "event_idx");
+		let mut statement = db
+			.prepare(&format!(
+				"SELECT e.block_number, b.hash, b.finalized, e.idx, e.extrinsic_idx, e.pallet,
+				e.name, e.args, e.error
+				FROM events e JOIN blocks b ON b.number = e.block_number
+				WHERE {} ORDER BY e.block_number, e.idx LIMIT {}",
+				conditions,
+				limit(filter),
+			))
+			.map_err(db_error)?;
+		let rows = statement
+			.query_map(&*params, |row| {
+				Ok(IndexedEvent {
+					block_number: row.get(0)?,
+					block_hash: Hash::from_slice(&row.get::<_, Vec<u8>>(1)?),
+					finalized: row.get(2)?,
+					index: row.get(3)?,
+					event: DecodedEvent {
+						extrinsic: row.get(4)?,
+						pallet: row.get(5)?,
+						name: row.get(6)?,
+						args: from_json(row.get(7)?).unwrap_or_default(),
+						error: row.get::<_, Option<String>>(8)?.and_then(from_json),
+					},
+				})
+			})
+			.map_err(db_error)?;
+		rows.collect::<Result<_, _>>().map_err(db_error)
+	}
+}
+
+/// SQL conditions of `filter` on the table aliased `table`, whose rows are referenced by the
+/// `accounts` column `account_column`.
+fn conditions(filter: &Filter, table: &str, account_column: &str) -> (String, Vec<Box<dyn ToSql>>) {
+	let mut conditions = vec!["1".to_string()];
+	let mut params = Vec::<Box<dyn ToSql>>::new();
+	if let Some(account) = &filter.account {
+		params.push(Box::new(account.clone()));
+		conditions.push(format!(
+			"EXISTS (SELECT 1 FROM accounts a WHERE a.account = ?{} \
+			 AND a.block_number = {table}.block_number AND a.{} = {table}.idx)",
+			params.len(),
+			account_column,
+			table = table,
+		));
+	}
+	if let Some(pallet) = &filter.pallet {
+		params.push(Box::new(pallet.clone()));
+		conditions.push(format!("{}.pallet = ?{}", table, params.len()));
+	}
+	if let Some(name) = &filter.name {
+		params.push(Box::new(name.clone()));
+		conditions.push(format!("{}.name = ?{}", table, params.len()));
+	}
+	if let Some(from) = filter.from {
+		params.push(Box::new(from));
+		conditions.push(format!("{}.block_number >= ?{}", table, params.len()));
+	}
+	if let Some(to) = filter.to {
+		params.push(Box::new(to));
+		conditions.push(format!("{}.block_number <= ?{}", table, params.len()));
+	}
+	(conditions.join(" AND "), params)
+}
+
+fn limit(filter: &Filter) -> u32 {
+	filter.limit.unwrap_or(100).min(MAX_LIMIT)
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+	serde_json::to_string(value).expect("indexed values serialize to JSON; qed")
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(json: String) -> Option<T> {
+	serde_json::from_str(&json).ok()
+}
+
+fn db_error(e: rusqlite::Error) -> String {
+	format!("Indexer database error: {}", e)
+}
@@ -1,3 +1,11 @@
 pub mod chain_spec;
+pub mod decoder;
+pub mod dispatch_error;
+pub mod encoder;
+pub mod health;
+pub mod indexer;
+pub mod metadata;
+pub mod metrics;
+pub mod pool_persistence;
 pub mod rpc;
 pub mod service;
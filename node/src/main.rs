@@ -6,6 +6,15 @@ mod chain_spec;
 mod service;
 mod cli;
 mod command;
+mod commands;
+mod decoder;
+mod dispatch_error;
+mod encoder;
+mod health;
+mod indexer;
+mod metadata;
+mod metrics;
+mod pool_persistence;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
@@ -0,0 +1,321 @@
+//! Node-side access to the metadata of the runtime.
+//!
+//! The metadata is decoded into owned types with every name resolved, which is easier to work
+//! with than the `DecodeDifferent` based types of `frame-metadata`.
+
+use codec::{Decode, Encode};
+use frame_metadata::{
+	DecodeDifferent, FunctionMetadata, ModuleMetadata, RuntimeMetadata, RuntimeMetadataPrefixed,
+	StorageEntryMetadata, StorageEntryType,
+};
+pub use frame_metadata::{StorageEntryModifier, StorageHasher};
+use node_template_runtime::{opaque::Block, Hash};
+use sc_executor::{NativeExecutor, WasmExecutionMethod};
+use sp_api::{Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_core::{
+	traits::{CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+	NeverNativeValue,
+};
+use sp_runtime::generic::BlockId;
+use sp_version::RuntimeVersion;
+use std::collections::HashMap;
+
+/// Metadata of a runtime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+	/// Pallets of the runtime, in the order of `construct_runtime!`.
+	pub pallets: Vec<PalletMetadata>,
+	/// Version of the extrinsic format.
+	pub extrinsic_version: u8,
+	/// Identifiers of the signed extensions, in the order of `SignedExtra`.
+	pub signed_extensions: Vec<String>,
+}
+
+/// Metadata of a pallet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PalletMetadata {
+	/// Name of the pallet in `construct_runtime!`.
+	pub name: String,
+	/// Index of the pallet, first byte of its calls, events and errors.
+	pub index: u8,
+	/// Prefix of the storage items of the pallet.
+	pub storage_prefix: String,
+	/// Storage items of the pallet.
+	pub storage: Vec<StorageMetadata>,
+	/// Dispatchable calls, indexed by their position.
+	pub calls: Vec<CallMetadata>,
+	/// Events, indexed by their position.
+	pub events: Vec<EventMetadata>,
+	/// Constants of the pallet's config.
+	pub constants: Vec<ConstantMetadata>,
+	/// Errors, indexed by their position.
+	pub errors: Vec<ErrorMetadata>,
+}
+
+/// Metadata of a dispatchable call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallMetadata {
+	/// Name of the call.
+	pub name: String,
+	/// Name and type of every argument.
+	pub arguments: Vec<(String, String)>,
+	/// Doc comment of the call.
+	pub documentation: Vec<String>,
+}
+
+/// Metadata of an event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventMetadata {
+	/// Name of the event.
+	pub name: String,
+	/// Type of every argument.
+	pub arguments: Vec<String>,
+	/// Doc comment of the event.
+	pub documentation: Vec<String>,
+}
+
+/// Metadata of a storage item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageMetadata {
+	/// Name of the storage item.
+	pub name: String,
+	/// Whether a missing value reads as `None` or as the default.
+	pub modifier: StorageEntryModifier,
+	/// Hasher and type of every key, empty for a storage value.
+	pub keys: Vec<(StorageHasher, String)>,
+	/// Type of the stored values.
+	pub value: String,
+	/// Encoded default value.
+	pub default: Vec<u8>,
+	/// Doc comment of the storage item.
+	pub documentation: Vec<String>,
+}
+
+/// Metadata of a constant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstantMetadata {
+	/// Name of the constant.
+	pub name: String,
+	/// Type of the constant.
+	pub ty: String,
+	/// Encoded value of the constant.
+	pub value: Vec<u8>,
+	/// Doc comment of the constant.
+	pub documentation: Vec<String>,
+}
+
+/// Metadata of an error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorMetadata {
+	/// Name of the error.
+	pub name: String,
+	/// Doc comment of the error.
+	pub documentation: Vec<String>,
+}
+
+impl Metadata {
+	/// Metadata of the runtime compiled natively into this node.
+	pub fn native() -> Self {
+		Self::decode(&native_encoded()).expect("native metadata is valid; qed")
+	}
+
+	/// Decode a SCALE encoded `RuntimeMetadataPrefixed`.
+	pub fn decode(encoded: &[u8]) -> Result<Self, String> {
+		let prefixed = RuntimeMetadataPrefixed::decode(&mut &encoded[..])
+			.map_err(|e| format!("Invalid metadata: {:?}", e))?;
+		let metadata = match prefixed.1 {
+			RuntimeMetadata::V13(metadata) => metadata,
+			_ => return Err("Only metadata V13 is supported".into()),
+		};
+
+		let pallets = decoded(&metadata.modules)?
+			.iter()
+			.map(pallet_metadata)
+			.collect::<Result<_, String>>()?;
+
+		Ok(Metadata {
+			pallets,
+			extrinsic_version: metadata.extrinsic.version,
+			signed_extensions: metadata
+				.extrinsic
+				.signed_extensions
+				.iter()
+				.map(|extension| decoded(extension).map(Clone::clone))
+				.collect::<Result<_, String>>()?,
+		})
+	}
+
+	/// Pallet with the given name.
+	pub fn pallet(&self, name: &str) -> Option<&PalletMetadata> {
+		self.pallets.iter().find(|pallet| pallet.name == name)
+	}
+
+	/// Pallet with the given index.
+	pub fn pallet_by_index(&self, index: u8) -> Option<&PalletMetadata> {
+		self.pallets.iter().find(|pallet| pallet.index == index)
+	}
+}
+
+/// Metadata of the runtimes met so far, by spec version.
+#[derive(Default)]
+pub struct MetadataCache(HashMap<u32, Metadata>);
+
+impl MetadataCache {
+	/// Metadata of the runtime at block `at`.
+	pub fn get<C>(&mut self, client: &C, at: Hash) -> Result<&Metadata, String>
+	where
+		C: ProvideRuntimeApi<Block>,
+		C::Api: MetadataApi<Block>,
+	{
+		let at = BlockId::Hash(at);
+		let version = client.runtime_api().version(&at).map_err(|e| e.to_string())?.spec_version;
+		if !self.0.contains_key(&version) {
+			let metadata = client.runtime_api().metadata(&at).map_err(|e| e.to_string())?;
+			self.0.insert(version, Metadata::decode(&metadata)?);
+		}
+		Ok(&self.0[&version])
+	}
+}
+
+/// SCALE encoded metadata of the runtime compiled natively into this node.
+pub fn native_encoded() -> Vec<u8> {
+	node_template_runtime::Runtime::metadata().encode()
+}
+
+/// Version and SCALE encoded metadata of a Wasm runtime blob.
+///
+/// The blob is executed without any state, as `Core_version` and `Metadata_metadata` don't need
+/// any.
+pub fn from_wasm(code: &[u8]) -> Result<(RuntimeVersion, Vec<u8>), String> {
+	let executor =
+		NativeExecutor::<crate::service::Executor>::new(WasmExecutionMethod::Interpreted, None, 1);
+	let code_fetcher = WrappedRuntimeCode(code.into());
+	let runtime_code = RuntimeCode {
+		code_fetcher: &code_fetcher,
+		heap_pages: None,
+		hash: sp_core::blake2_256(code).to_vec(),
+	};
+
+	let call = |method: &str| -> Result<Vec<u8>, String> {
+		let mut ext = sp_state_machine::BasicExternalities::new_empty();
+		executor
+			.call::<NeverNativeValue, fn() -> _>(&mut ext, &runtime_code, method, &[], false, None)
+			.0
+			.map(|result| result.into_encoded())
+			.map_err(|e| format!("Failed to call `{}`: {:?}", method, e))
+	};
+
+	let version = RuntimeVersion::decode(&mut &call("Core_version")?[..])
+		.map_err(|e| format!("Invalid runtime version: {:?}", e))?;
+	// `Metadata_metadata` returns an `OpaqueMetadata`, i.e. the encoded metadata as a `Vec<u8>`.
+	let metadata = Vec::<u8>::decode(&mut &call("Metadata_metadata")?[..])
+		.map_err(|e| format!("Invalid opaque metadata: {:?}", e))?;
+
+	Ok((version, metadata))
+}
+
+fn pallet_metadata(module: &ModuleMetadata) -> Result<PalletMetadata, String> {
+	let storage = match &module.storage {
+		Some(storage) => Some(decoded(storage)?),
+		None => None,
+	};
+
+	Ok(PalletMetadata {
+		name: decoded(&module.name)?.clone(),
+		index: module.index,
+		storage_prefix: match storage {
+			Some(storage) => decoded(&storage.prefix)?.clone(),
+			None => String::new(),
+		},
+		storage: match storage {
+			Some(storage) => decoded(&storage.entries)?
+				.iter()
+				.map(storage_metadata)
+				.collect::<Result<_, String>>()?,
+			None => vec![],
+		},
+		calls: match &module.calls {
+			Some(calls) =>
+				decoded(calls)?.iter().map(call_metadata).collect::<Result<_, String>>()?,
+			None => vec![],
+		},
+		events: match &module.event {
+			Some(events) => decoded(events)?
+				.iter()
+				.map(|event| {
+					Ok(EventMetadata {
+						name: decoded(&event.name)?.clone(),
+						arguments: decoded(&event.arguments)?.clone(),
+						documentation: decoded(&event.documentation)?.clone(),
+					})
+				})
+				.collect::<Result<_, String>>()?,
+			None => vec![],
+		},
+		constants: decoded(&module.constants)?
+			.iter()
+			.map(|constant| {
+				Ok(ConstantMetadata {
+					name: decoded(&constant.name)?.clone(),
+					ty: decoded(&constant.ty)?.clone(),
+					value: decoded(&constant.value)?.clone(),
+					documentation: decoded(&constant.documentation)?.clone(),
+				})
+			})
+			.collect::<Result<_, String>>()?,
+		errors: decoded(&module.errors)?
+			.iter()
+			.map(|error| {
+				Ok(ErrorMetadata {
+					name: decoded(&error.name)?.clone(),
+					documentation: decoded(&error.documentation)?.clone(),
+				})
+			})
+			.collect::<Result<_, String>>()?,
+	})
+}
+
+fn storage_metadata(entry: &StorageEntryMetadata) -> Result<StorageMetadata, String> {
+	let (keys, value) = match &entry.ty {
+		StorageEntryType::Plain(value) => (vec![], value),
+		StorageEntryType::Map { hasher, key, value, .. } =>
+			(vec![(hasher.clone(), decoded(key)?.clone())], value),
+		StorageEntryType::DoubleMap { hasher, key1, key2, value, key2_hasher } => (
+			vec![
+				(hasher.clone(), decoded(key1)?.clone()),
+				(key2_hasher.clone(), decoded(key2)?.clone()),
+			],
+			value,
+		),
+		StorageEntryType::NMap { keys, hashers, value } =>
+			(decoded(hashers)?.iter().cloned().zip(decoded(keys)?.iter().cloned()).collect(), value),
+	};
+
+	Ok(StorageMetadata {
+		name: decoded(&entry.name)?.clone(),
+		modifier: entry.modifier.clone(),
+		keys,
+		value: decoded(value)?.clone(),
+		default: decoded(&entry.default)?.clone(),
+		documentation: decoded(&entry.documentation)?.clone(),
+	})
+}
+
+fn call_metadata(call: &FunctionMetadata) -> Result<CallMetadata, String> {
+	Ok(CallMetadata {
+		name: decoded(&call.name)?.clone(),
+		arguments: decoded(&call.arguments)?
+			.iter()
+			.map(|arg| Ok((decoded(&arg.name)?.clone(), decoded(&arg.ty)?.clone())))
+			.collect::<Result<_, String>>()?,
+		documentation: decoded(&call.documentation)?.clone(),
+	})
+}
+
+/// Value of a field of metadata that was decoded from its SCALE encoding.
+fn decoded<B, O>(value: &DecodeDifferent<B, O>) -> Result<&O, String> {
+	match value {
+		DecodeDifferent::Decoded(value) => Ok(value),
+		DecodeDifferent::Encode(_) => Err("Metadata was not decoded".into()),
+	}
+}
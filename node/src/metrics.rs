@@ -0,0 +1,378 @@
+//! Prometheus metrics specific to this node.
+//!
+//! Metrics are registered in the registry of the node, so their names get the same `substrate_`
+//! prefix as the ones of Substrate.
+
+use futures::{
+	future::{BoxFuture, Either},
+	stream, FutureExt, StreamExt, TryFutureExt,
+};
+use node_template_runtime::{opaque::Block, BlockNumber, Hash};
+use prometheus_endpoint::{
+	exponential_buckets, register, Counter, CounterVec, Gauge, Histogram, HistogramOpts, Opts,
+	PrometheusError, Registry, U64,
+};
+use sc_client_api::BlockchainEvents;
+use sc_consensus_slots::BackoffAuthoringBlocksStrategy;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::{BlockOrigin, Environment, Proposal, Proposer};
+use sp_consensus_aura::{
+	sr25519::{AuthorityId as AuraId, AuthoritySignature as AuraSignature},
+	AuraApi, Slot,
+};
+use sp_core::crypto::{key_types::AURA, Public, Ss58Codec};
+use sp_inherents::InherentData;
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, DigestFor, Header as _},
+};
+use std::{
+	collections::HashMap,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+/// How a full node follows GRANDPA finality.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrandpaMode {
+	/// The node runs the full voter and takes part in vote gossip.
+	Voter,
+	/// The node runs the observer and only follows commit messages.
+	Observer,
+}
+
+impl GrandpaMode {
+	fn as_str(&self) -> &'static str {
+		match self {
+			GrandpaMode::Voter => "voter",
+			GrandpaMode::Observer => "observer",
+		}
+	}
+}
+
+/// Distance between the best and the finalized block, labelled with the [`GrandpaMode`] so that
+/// voters and observers of a fleet can be compared.
+pub struct FinalityLagMetrics {
+	lag: Gauge<U64>,
+	delay: Histogram,
+}
+
+impl FinalityLagMetrics {
+	/// Register the metrics of a node running in `mode`.
+	pub fn register(registry: &Registry, mode: GrandpaMode) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			lag: register(
+				Gauge::with_opts(
+					Opts::new(
+						"finality_lag_blocks",
+						"Number of blocks between the best and the finalized block",
+					)
+					.const_label("mode", mode.as_str()),
+				)?,
+				registry,
+			)?,
+			delay: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"finality_delay_seconds",
+						"Time between the import of a block and its finalization",
+					)
+					.const_label("mode", mode.as_str())
+					.buckets(exponential_buckets(1.0, 2.0, 10)?),
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Update the metrics on every block import and finality notification of `client`.
+	pub async fn run<C>(self, client: Arc<C>)
+	where
+		C: BlockchainEvents<Block> + HeaderBackend<Block>,
+	{
+		let imports = client.import_notification_stream().map(Either::Left);
+		let finality = client.finality_notification_stream().map(Either::Right);
+		let mut notifications = stream::select(imports, finality);
+		// Blocks that were imported but are not finalized yet.
+		let mut pending = HashMap::<Hash, (BlockNumber, Instant)>::new();
+
+		while let Some(notification) = notifications.next().await {
+			match notification {
+				Either::Left(import) => {
+					pending.insert(import.hash, (*import.header.number(), Instant::now()));
+				},
+				Either::Right(finality) => {
+					let finalized = *finality.header.number();
+					pending.retain(|hash, (number, imported)| {
+						if *number > finalized {
+							return true
+						}
+						// Blocks of abandoned forks are dropped without being observed.
+						if client.hash(*number).ok().flatten().as_ref() == Some(hash) {
+							self.delay.observe(imported.elapsed().as_secs_f64());
+						}
+						false
+					});
+				},
+			}
+
+			let info = client.info();
+			self.lag.set(info.best_number.saturating_sub(info.finalized_number).into());
+		}
+	}
+}
+
+/// Backoff strategy that counts the slots it makes the node skip.
+pub struct MeteredBackoff<S> {
+	inner: S,
+	skipped_slots: Option<Counter<U64>>,
+}
+
+impl<S> MeteredBackoff<S> {
+	/// Wrap `inner`, registering the counter if the node exposes metrics.
+	pub fn new(inner: S, registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+		let skipped_slots = match registry {
+			Some(registry) => Some(register(
+				Counter::new(
+					"authoring_skipped_slots_total",
+					"Number of slots in which authoring was skipped because finality lags",
+				)?,
+				registry,
+			)?),
+			None => None,
+		};
+		Ok(Self { inner, skipped_slots })
+	}
+}
+
+impl<N, S: BackoffAuthoringBlocksStrategy<N>> BackoffAuthoringBlocksStrategy<N>
+	for MeteredBackoff<S>
+{
+	fn should_backoff(
+		&self,
+		chain_head_number: N,
+		chain_head_slot: Slot,
+		finalized_number: N,
+		slot_now: Slot,
+		logging_target: &str,
+	) -> bool {
+		let backoff = self.inner.should_backoff(
+			chain_head_number,
+			chain_head_slot,
+			finalized_number,
+			slot_now,
+			logging_target,
+		);
+		if let (true, Some(skipped_slots)) = (backoff, &self.skipped_slots) {
+			skipped_slots.inc();
+		}
+		backoff
+	}
+}
+
+/// Slots owned by the authorities of the local keystore, and how many of them are missing a block
+/// in the best chain.
+pub struct SlotMetrics {
+	claimed: CounterVec<U64>,
+	missed: CounterVec<U64>,
+}
+
+impl SlotMetrics {
+	/// Register the metrics, labelled with the SS58 address of each local authority.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			claimed: register(
+				CounterVec::new(
+					Opts::new(
+						"aura_slots_claimed_total",
+						"Number of slots assigned to a local authority",
+					),
+					&["authority"],
+				)?,
+				registry,
+			)?,
+			missed: register(
+				CounterVec::new(
+					Opts::new(
+						"aura_slots_missed_total",
+						"Number of slots assigned to a local authority without a block in the best \
+						 chain",
+					),
+					&["authority"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Follow the best chain of `client` and account for every slot it moves past.
+	///
+	/// A slot is missed if the best chain goes from a block of an earlier slot to a block of a
+	/// later one, whether the local authority did not author or its block lost a fork.
+	pub async fn run<C>(self, client: Arc<C>, keystore: SyncCryptoStorePtr)
+	where
+		C: BlockchainEvents<Block> + HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+		C::Api: AuraApi<Block, AuraId>,
+	{
+		let mut imports = client.import_notification_stream();
+		// Last slot that was accounted for.
+		let mut last_slot = None::<u64>;
+
+		while let Some(import) = imports.next().await {
+			if !import.is_new_best {
+				continue
+			}
+			let slot =
+				match sc_consensus_aura::find_pre_digest::<Block, AuraSignature>(&import.header) {
+					Ok(slot) => *slot,
+					Err(_) => continue,
+				};
+			let from = match last_slot {
+				// Slots of a fork switch were already accounted for.
+				Some(last) if last >= slot => continue,
+				Some(last) if import.origin != BlockOrigin::NetworkInitialSync => last + 1,
+				// Only the slots after the node caught up with the network are accounted for.
+				_ => slot,
+			};
+			last_slot = Some(slot);
+
+			let parent = BlockId::Hash(*import.header.parent_hash());
+			let authorities = match client.runtime_api().authorities(&parent) {
+				Ok(authorities) if !authorities.is_empty() => authorities,
+				_ => continue,
+			};
+
+			for current in from..=slot {
+				let owner = &authorities[(current % authorities.len() as u64) as usize];
+				if !SyncCryptoStore::has_keys(&*keystore, &[(owner.to_raw_vec(), AURA)]) {
+					continue
+				}
+				let label = sp_core::sr25519::Public::from(owner.clone()).to_ss58check();
+				self.claimed.with_label_values(&[&label]).inc();
+				if current != slot {
+					self.missed.with_label_values(&[&label]).inc();
+				}
+			}
+		}
+	}
+}
+
+/// Duration and size of the blocks proposed by the node.
+#[derive(Clone)]
+pub struct ProposerMetrics {
+	duration: Histogram,
+	slot_portion: Histogram,
+	extrinsics: Histogram,
+}
+
+impl ProposerMetrics {
+	/// Register the metrics.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			duration: register(
+				Histogram::with_opts(
+					HistogramOpts::new("proposal_duration_seconds", "Time spent building a block")
+						.buckets(exponential_buckets(0.01, 2.0, 12)?),
+				)?,
+				registry,
+			)?,
+			slot_portion: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"proposal_slot_portion",
+						"Time spent building a block, relative to the time it was given by \
+						 `block_proposal_slot_portion`",
+					)
+					.buckets(vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0, 1.5, 2.0]),
+				)?,
+				registry,
+			)?,
+			extrinsics: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"proposal_extrinsics",
+						"Number of extrinsics, inherents included, in a proposed block",
+					)
+					.buckets(exponential_buckets(1.0, 2.0, 12)?),
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Proposer factory recording [`ProposerMetrics`] for the proposers it creates.
+pub struct MeteredProposerFactory<E> {
+	inner: E,
+	metrics: Option<ProposerMetrics>,
+}
+
+impl<E> MeteredProposerFactory<E> {
+	/// Wrap `inner`, registering the metrics if the node exposes metrics.
+	pub fn new(inner: E, registry: Option<&Registry>) -> Result<Self, PrometheusError> {
+		Ok(Self { inner, metrics: registry.map(ProposerMetrics::register).transpose()? })
+	}
+}
+
+impl<E> Environment<Block> for MeteredProposerFactory<E>
+where
+	E: Environment<Block>,
+	E::Error: Send,
+	<E::Proposer as Proposer<Block>>::Error: Send,
+{
+	type Proposer = MeteredProposer<E::Proposer>;
+	type CreateProposer = BoxFuture<'static, Result<Self::Proposer, Self::Error>>;
+	type Error = E::Error;
+
+	fn init(&mut self, parent_header: &<Block as BlockT>::Header) -> Self::CreateProposer {
+		let metrics = self.metrics.clone();
+		self.inner
+			.init(parent_header)
+			.map_ok(move |inner| MeteredProposer { inner, metrics })
+			.boxed()
+	}
+}
+
+/// Proposer recording [`ProposerMetrics`] for the block it proposes.
+pub struct MeteredProposer<P> {
+	inner: P,
+	metrics: Option<ProposerMetrics>,
+}
+
+impl<P> Proposer<Block> for MeteredProposer<P>
+where
+	P: Proposer<Block>,
+	P::Error: Send,
+{
+	type Error = P::Error;
+	type Transaction = P::Transaction;
+	type Proposal = BoxFuture<'static, Result<Proposal<Block, P::Transaction, P::Proof>, P::Error>>;
+	type ProofRecording = P::ProofRecording;
+	type Proof = P::Proof;
+
+	fn propose(
+		self,
+		inherent_data: InherentData,
+		inherent_digests: DigestFor<Block>,
+		max_duration: Duration,
+		block_size_limit: Option<usize>,
+	) -> Self::Proposal {
+		let started = Instant::now();
+		let metrics = self.metrics;
+
+		self.inner
+			.propose(inherent_data, inherent_digests, max_duration, block_size_limit)
+			.map(move |result| {
+				if let (Ok(proposal), Some(metrics)) = (&result, metrics) {
+					let elapsed = started.elapsed().as_secs_f64();
+					metrics.duration.observe(elapsed);
+					metrics.slot_portion.observe(elapsed / max_duration.as_secs_f64());
+					metrics.extrinsics.observe(proposal.block.extrinsics().len() as f64);
+				}
+				result
+			})
+			.boxed()
+	}
+}
@@ -0,0 +1,126 @@
+//! Persistence of the transaction pool across restarts.
+//!
+//! The pending extrinsics of the pool are written to a file of the chain directory when the node
+//! shuts down. On the next start, the file is read, removed, and its extrinsics are submitted
+//! again, so that they are revalidated against the new best block.
+
+use codec::{Decode, Encode};
+use node_template_runtime::opaque::Block;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool, TransactionSource};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+type Extrinsic = <Block as BlockT>::Extrinsic;
+
+/// Name of the file in the chain directory.
+pub const FILE_NAME: &str = "txpool";
+
+/// Read and remove the extrinsics saved by the previous run, if any.
+///
+/// The file is removed even if it is invalid or larger than `max_size`, so that a bad file is
+/// not read at every start.
+pub fn load(path: &Path, max_size: usize) -> Vec<Extrinsic> {
+	let raw = match fs::read(path) {
+		Ok(raw) => raw,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+		Err(e) => {
+			log::warn!("Failed to read the saved transactions from {}: {}", path.display(), e);
+			return Vec::new()
+		},
+	};
+	if let Err(e) = fs::remove_file(path) {
+		log::warn!("Failed to remove {}: {}", path.display(), e);
+	}
+
+	if raw.len() > max_size {
+		log::warn!(
+			"Ignoring the saved transactions, {} is {} bytes large, more than {} bytes",
+			path.display(),
+			raw.len(),
+			max_size,
+		);
+		return Vec::new()
+	}
+	Vec::<Extrinsic>::decode(&mut &raw[..]).unwrap_or_else(|e| {
+		log::warn!("Ignoring the saved transactions, {} is invalid: {:?}", path.display(), e);
+		Vec::new()
+	})
+}
+
+/// Submit `extrinsics` to `pool`, validating them at the block `at`.
+pub async fn restore<P>(pool: Arc<P>, at: BlockId<Block>, extrinsics: Vec<Extrinsic>)
+where
+	P: TransactionPool<Block = Block>,
+{
+	let total = extrinsics.len();
+	match pool.submit_at(&at, TransactionSource::External, extrinsics).await {
+		Ok(results) => log::info!(
+			"Restored {} of {} saved transactions",
+			results.iter().filter(|result| result.is_ok()).count(),
+			total,
+		),
+		Err(e) => log::warn!("Failed to restore the saved transactions: {}", e),
+	}
+}
+
+/// Saves the pending extrinsics of the pool when dropped, i.e. when the node shuts down.
+pub struct SaveOnDrop<P: TransactionPool<Block = Block>> {
+	pool: Arc<P>,
+	path: PathBuf,
+	max_size: usize,
+}
+
+impl<P: TransactionPool<Block = Block>> SaveOnDrop<P> {
+	/// Save the extrinsics of `pool` to `path`, up to `max_size` bytes.
+	pub fn new(pool: Arc<P>, path: PathBuf, max_size: usize) -> Self {
+		Self { pool, path, max_size }
+	}
+
+	fn save(&self) -> io::Result<()> {
+		// Ready extrinsics come first, they are the ones that were about to be included.
+		let pending = self
+			.pool
+			.ready()
+			.map(|tx| tx.data().clone())
+			.chain(self.pool.futures().into_iter().map(|tx| tx.data().clone()));
+
+		let mut extrinsics = Vec::new();
+		// Leave room for the length prefix of the vector.
+		let mut size = 5;
+		let mut dropped = 0;
+		for extrinsic in pending {
+			let len = extrinsic.encoded_size();
+			if size + len > self.max_size {
+				dropped += 1;
+				continue
+			}
+			size += len;
+			extrinsics.push(extrinsic);
+		}
+		if dropped > 0 {
+			log::warn!("Not saving {} transactions, the file would exceed its size limit", dropped);
+		}
+		if extrinsics.is_empty() {
+			return Ok(())
+		}
+
+		// Write to a temporary file first, so that a crash never leaves a truncated file behind.
+		let tmp = self.path.with_extension("tmp");
+		fs::write(&tmp, extrinsics.encode())?;
+		fs::rename(&tmp, &self.path)?;
+		log::info!("Saved {} transactions to {}", extrinsics.len(), self.path.display());
+		Ok(())
+	}
+}
+
+impl<P: TransactionPool<Block = Block>> Drop for SaveOnDrop<P> {
+	fn drop(&mut self) {
+		if let Err(e) = self.save() {
+			log::warn!("Failed to save the transactions to {}: {}", self.path.display(), e);
+		}
+	}
+}
@@ -0,0 +1,58 @@
+//! Names and documentation of dispatch errors.
+
+use crate::{dispatch_error::DecodedError, metadata::Metadata};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_template_runtime::{opaque::Block, Hash};
+use sp_api::{Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::generic::BlockId;
+use std::sync::Arc;
+
+/// Dispatch error RPC methods.
+#[rpc(server)]
+pub trait DispatchErrorApi<BlockHash> {
+	/// Pallet, name and documentation of the error `Module { index, error }`, as known to the
+	/// runtime of block `at` or of the best block.
+	#[rpc(name = "dev_decodeModuleError")]
+	fn decode_module_error(
+		&self,
+		index: u8,
+		error: u8,
+		at: Option<BlockHash>,
+	) -> Result<DecodedError>;
+}
+
+/// Implementation of [`DispatchErrorApi`].
+pub struct DispatchErrors<C> {
+	client: Arc<C>,
+}
+
+impl<C> DispatchErrors<C> {
+	/// Create new `DispatchErrors` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> DispatchErrorApi<Hash> for DispatchErrors<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: MetadataApi<Block>,
+{
+	fn decode_module_error(&self, index: u8, error: u8, at: Option<Hash>) -> Result<DecodedError> {
+		let at = BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let metadata = self.client.runtime_api().metadata(&at).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(1),
+			message: "Unable to read the metadata.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+		let metadata = Metadata::decode(&metadata).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(1),
+			message: "Invalid metadata.".into(),
+			data: Some(e.into()),
+		})?;
+
+		Ok(DecodedError::module(index, error, &metadata))
+	}
+}
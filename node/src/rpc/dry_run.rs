@@ -0,0 +1,258 @@
+//! Preview of extrinsics and calls on top of a block.
+//!
+//! `dev_dryRun` is unsafe, as executing arbitrary calls can be expensive.
+
+use crate::{
+	dispatch_error::{event_error, DecodedError},
+	metadata::Metadata,
+};
+use codec::{Decode, Encode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_template_runtime::{
+	dry_run::{self, DryRunApi as DryRunRuntimeApi, DryRunEffects},
+	opaque::Block,
+	AccountId, Call, Hash,
+};
+use sc_client_api::backend::{Backend, StateBackendFor};
+use sc_rpc_api::DenyUnsafe;
+use serde::{Deserialize, Serialize};
+use sp_api::{ApiExt, Core, Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	storage::{StorageData, StorageKey},
+	Bytes,
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as _},
+};
+use std::{fmt::Debug, sync::Arc};
+
+/// Origin to dispatch a call with.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DryRunOrigin {
+	/// The root origin, as used by `Sudo`.
+	Root,
+	/// A signed origin.
+	Signed(AccountId),
+	/// An unsigned origin.
+	None,
+}
+
+impl From<DryRunOrigin> for dry_run::DryRunOrigin {
+	fn from(origin: DryRunOrigin) -> Self {
+		match origin {
+			DryRunOrigin::Root => Self::Root,
+			DryRunOrigin::Signed(who) => Self::Signed(who),
+			DryRunOrigin::None => Self::None,
+		}
+	}
+}
+
+/// Effects of a dry-run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResult {
+	/// Whether the extrinsic is valid and its call succeeded.
+	pub success: bool,
+	/// Why the extrinsic is invalid, it was not dispatched then.
+	pub invalid: Option<String>,
+	/// Why the dispatch failed.
+	pub error: Option<DecodedError>,
+	/// Events emitted during the dispatch.
+	pub events: Vec<EventInfo>,
+	/// Weight actually consumed.
+	pub weight: u64,
+	/// Storage keys changed by the dispatch, with their new value or `None` if removed.
+	pub storage_changes: Vec<(StorageKey, Option<StorageData>)>,
+}
+
+/// An event emitted during a dry-run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EventInfo {
+	/// Pallet the event belongs to.
+	pub pallet: Option<String>,
+	/// Name of the event.
+	pub name: Option<String>,
+	/// Error reported by the event, e.g. by `System::ExtrinsicFailed`.
+	pub error: Option<DecodedError>,
+	/// The event as printed by the node.
+	pub description: String,
+	/// The SCALE encoded event.
+	pub data: Bytes,
+}
+
+/// Dry-run RPC methods.
+#[rpc(server)]
+pub trait DryRunApi<BlockHash> {
+	/// Apply an extrinsic, or dispatch a call with `origin`, on top of block `at` or of the best
+	/// block, without committing anything.
+	///
+	/// `payload` is an encoded extrinsic, as for `author_submitExtrinsic`, unless `origin` is
+	/// given, in which case it is an encoded `Call`.
+	#[rpc(name = "dev_dryRun")]
+	fn dry_run(
+		&self,
+		payload: Bytes,
+		origin: Option<DryRunOrigin>,
+		at: Option<BlockHash>,
+	) -> Result<DryRunResult>;
+}
+
+/// Implementation of [`DryRunApi`].
+pub struct DryRun<C, B> {
+	client: Arc<C>,
+	backend: Arc<B>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<C, B> DryRun<C, B> {
+	/// Create new `DryRun` with the given reference to the client and its backend.
+	pub fn new(client: Arc<C>, backend: Arc<B>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, backend, deny_unsafe }
+	}
+}
+
+impl<C, B> DryRunApi<Hash> for DryRun<C, B>
+where
+	B: Backend<Block> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DryRunRuntimeApi<Block> + MetadataApi<Block>,
+	C::Api: ApiExt<Block, StateBackend = StateBackendFor<B, Block>>,
+{
+	fn dry_run(
+		&self,
+		payload: Bytes,
+		origin: Option<DryRunOrigin>,
+		at: Option<Hash>,
+	) -> Result<DryRunResult> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let parent_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let parent = BlockId::Hash(parent_hash);
+		let number = self
+			.client
+			.number(parent_hash)
+			.map_err(|e| error("Unable to read the block", e))?
+			.ok_or_else(|| error("Unknown block", parent_hash))?;
+		let header = <Block as BlockT>::Header::new(
+			number + 1,
+			Default::default(),
+			Default::default(),
+			parent_hash,
+			Default::default(),
+		);
+
+		// Changes of the block initialization alone, which are not reported.
+		let initialization = {
+			let api = self.client.runtime_api();
+			api.initialize_block(&parent, &header)
+				.map_err(|e| error("Unable to initialize the block", e))?;
+			self.storage_changes(&*api, parent_hash)?
+		};
+
+		let api = self.client.runtime_api();
+		api.initialize_block(&parent, &header)
+			.map_err(|e| error("Unable to initialize the block", e))?;
+		let effects = match origin {
+			None => {
+				let xt = <Block as BlockT>::Extrinsic::decode(&mut &*payload)
+					.map_err(|e| error("Unable to decode the extrinsic", e))?;
+				api.dry_run_extrinsic(&parent, xt)
+			},
+			Some(origin) => {
+				let call = Call::decode(&mut &*payload)
+					.map_err(|e| error("Unable to decode the call", e))?;
+				api.dry_run_call(&parent, origin.into(), call)
+			},
+		}
+		.map_err(|e| error("Unable to dry-run", e))?;
+		let metadata =
+			api.metadata(&parent).map_err(|e| error("Unable to read the metadata", e))?;
+		let metadata = Metadata::decode(&metadata).map_err(|e| error("Invalid metadata", e))?;
+		let storage_changes = self
+			.storage_changes(&*api, parent_hash)?
+			.into_iter()
+			.filter(|change| !initialization.contains(change))
+			.map(|(key, value)| (StorageKey(key), value.map(StorageData)))
+			.collect();
+
+		Ok(describe(effects, &metadata, storage_changes))
+	}
+}
+
+impl<C, B> DryRun<C, B>
+where
+	B: Backend<Block>,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: ApiExt<Block, StateBackend = StateBackendFor<B, Block>>,
+{
+	/// Storage changes made through `api` on top of the state of `parent_hash`.
+	fn storage_changes(
+		&self,
+		api: &C::Api,
+		parent_hash: Hash,
+	) -> Result<Vec<(Vec<u8>, Option<Vec<u8>>)>> {
+		let state = self
+			.backend
+			.state_at(BlockId::Hash(parent_hash))
+			.map_err(|e| error("Unable to read the state", e))?;
+		let changes = api
+			.into_storage_changes(&state, None, parent_hash)
+			.map_err(|e| error("Unable to collect the storage changes", e))?;
+		Ok(changes.main_storage_changes)
+	}
+}
+
+/// Describe `effects` with the names of `metadata`.
+fn describe(
+	effects: DryRunEffects,
+	metadata: &Metadata,
+	storage_changes: Vec<(StorageKey, Option<StorageData>)>,
+) -> DryRunResult {
+	let (invalid, error) = match effects.result {
+		Ok(Ok(())) => (None, None),
+		Ok(Err(error)) => (None, Some(DecodedError::new(&error, metadata))),
+		Err(validity_error) => (Some(format!("{:?}", validity_error)), None),
+	};
+
+	let events = effects
+		.events
+		.into_iter()
+		.map(|record| {
+			let data = record.event.encode();
+			// Events are encoded as the index of their pallet, then the index of the event.
+			let pallet = data.get(0).and_then(|index| metadata.pallet_by_index(*index));
+			let name = data
+				.get(1)
+				.and_then(|index| pallet?.events.get(*index as usize))
+				.map(|event| event.name.clone());
+			EventInfo {
+				pallet: pallet.map(|pallet| pallet.name.clone()),
+				name,
+				error: event_error(&record.event).map(|error| DecodedError::new(error, metadata)),
+				description: format!("{:?}", record.event),
+				data: data.into(),
+			}
+		})
+		.collect();
+
+	DryRunResult {
+		success: invalid.is_none() && error.is_none(),
+		invalid,
+		error,
+		events,
+		weight: effects.weight,
+		storage_changes,
+	}
+}
+
+fn error(message: &str, e: impl Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: message.into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
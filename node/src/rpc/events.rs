@@ -0,0 +1,206 @@
+//! Subscription to decoded events.
+//!
+//! Events are decoded with the metadata of the runtime that deposited them, then filtered on the
+//! node, so that clients are only notified of the events they are interested in.
+
+use crate::{
+	decoder::{events_key, DecodedEvent, Decoder},
+	metadata::MetadataCache,
+};
+use futures::{future, stream, FutureExt, SinkExt, StreamExt};
+use jsonrpc_core::{Error as RpcError, Result};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use node_template_runtime::{opaque::Block, AccountId, BlockNumber, Hash};
+use sc_client_api::{
+	backend::{Backend, StateBackend},
+	BlockchainEvents,
+};
+use sc_rpc::SubscriptionTaskExecutor;
+use serde::{Deserialize, Serialize};
+use sp_api::{Metadata as MetadataApi, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Header as _};
+use std::sync::Arc;
+
+/// Events to be notified of, every given criterion must match.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EventFilter {
+	/// Pallet of the event, e.g. `TemplateModule`.
+	pub pallet: Option<String>,
+	/// Name of the event, e.g. `SomethingStored`.
+	pub name: Option<String>,
+	/// Account among the arguments of the event.
+	pub account: Option<AccountId>,
+}
+
+/// An event matching a subscription.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchedEvent<Hash> {
+	/// Hash of the block.
+	pub block_hash: Hash,
+	/// Number of the block.
+	pub block_number: BlockNumber,
+	/// Whether the block is finalized.
+	pub finalized: bool,
+	/// The event, with the index of the extrinsic that deposited it.
+	#[serde(flatten)]
+	pub event: DecodedEvent,
+}
+
+/// Event RPC methods.
+#[rpc(server)]
+pub trait EventsApi<BlockHash> {
+	/// RPC metadata
+	type Metadata;
+
+	/// Subscribe to the events matching `filter`.
+	///
+	/// The events of a block are notified when it becomes the best block, then again once it is
+	/// finalized.
+	#[pubsub(subscription = "events", subscribe, name = "events_subscribe")]
+	fn subscribe(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<MatchedEvent<BlockHash>>,
+		filter: EventFilter,
+	);
+
+	/// Cancel a subscription to events.
+	#[pubsub(subscription = "events", unsubscribe, name = "events_unsubscribe")]
+	fn unsubscribe(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+}
+
+/// Implementation of [`EventsApi`].
+pub struct Events<C, B> {
+	client: Arc<C>,
+	backend: Arc<B>,
+	subscriptions: SubscriptionManager,
+}
+
+impl<C, B> Events<C, B> {
+	/// Create new `Events` running its subscriptions with `executor`.
+	pub fn new(client: Arc<C>, backend: Arc<B>, executor: SubscriptionTaskExecutor) -> Self {
+		Self { client, backend, subscriptions: SubscriptionManager::new(Arc::new(executor)) }
+	}
+}
+
+impl<C, B> EventsApi<Hash> for Events<C, B>
+where
+	B: Backend<Block> + Send + Sync + 'static,
+	C: BlockchainEvents<Block> + HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+	C: Send + Sync + 'static,
+	C::Api: MetadataApi<Block>,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn subscribe(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<MatchedEvent<Hash>>,
+		filter: EventFilter,
+	) {
+		let best = self.client.import_notification_stream().filter_map(|import| {
+			future::ready(if import.is_new_best { Some(vec![(import.hash, false)]) } else { None })
+		});
+		let finalized = {
+			let client = self.client.clone();
+			let mut last = client.info().finalized_number;
+			// A notification can finalize several blocks at once.
+			self.client.finality_notification_stream().map(move |notification| {
+				let number = *notification.header.number();
+				let hashes = (last + 1..=number)
+					.filter_map(|n| client.hash(n).ok().flatten())
+					.map(|hash| (hash, true))
+					.collect::<Vec<_>>();
+				last = last.max(number);
+				hashes
+			})
+		};
+		let mut matcher = Matcher {
+			client: self.client.clone(),
+			backend: self.backend.clone(),
+			filter,
+			metadata: Default::default(),
+		};
+
+		self.subscriptions.add(subscriber, move |sink| {
+			stream::select(best, finalized)
+				.flat_map(stream::iter)
+				.flat_map(move |(hash, finalized)| stream::iter(matcher.matches(hash, finalized)))
+				.map(|event| Ok::<_, ()>(Ok::<_, RpcError>(event)))
+				.forward(sink.sink_map_err(|e| log::warn!("Error sending notifications: {:?}", e)))
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
+
+/// Decodes the events of blocks and keeps the ones matching a filter.
+struct Matcher<C, B> {
+	client: Arc<C>,
+	backend: Arc<B>,
+	filter: EventFilter,
+	metadata: MetadataCache,
+}
+
+impl<C, B> Matcher<C, B>
+where
+	B: Backend<Block>,
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+	C::Api: MetadataApi<Block>,
+{
+	fn matches(&mut self, hash: Hash, finalized: bool) -> Vec<MatchedEvent<Hash>> {
+		self.try_matches(hash, finalized).unwrap_or_else(|e| {
+			log::warn!("Unable to decode the events of block {}: {}", hash, e);
+			Vec::new()
+		})
+	}
+
+	fn try_matches(
+		&mut self,
+		hash: Hash,
+		finalized: bool,
+	) -> std::result::Result<Vec<MatchedEvent<Hash>>, String> {
+		let at = BlockId::Hash(hash);
+		let header = self
+			.client
+			.header(at)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Unknown block {}", hash))?;
+		let events = self
+			.backend
+			.state_at(at)
+			.map_err(|e| e.to_string())?
+			.storage(&events_key())
+			.map_err(|e| e.to_string())?
+			.unwrap_or_default();
+		if events.is_empty() {
+			return Ok(Vec::new())
+		}
+
+		// The events were deposited by the runtime of the parent block.
+		let metadata = self.metadata.get(&*self.client, *header.parent_hash())?;
+		let filter = &self.filter;
+		Ok(Decoder::new(metadata)
+			.events(&mut &events[..])?
+			.into_iter()
+			.filter(|(event, accounts)| {
+				filter.pallet.as_ref().map_or(true, |pallet| *pallet == event.pallet) &&
+					filter.name.as_ref().map_or(true, |name| *name == event.name) &&
+					filter.account.as_ref().map_or(true, |account| accounts.contains(account))
+			})
+			.map(|(event, _)| MatchedEvent {
+				block_hash: hash,
+				block_number: *header.number(),
+				finalized,
+				event,
+			})
+			.collect())
+	}
+}
@@ -0,0 +1,48 @@
+//! Queries of the chain indexer.
+
+use crate::indexer::{Filter, Index, IndexedEvent, IndexedExtrinsic};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use std::sync::Arc;
+
+/// Indexer RPC methods.
+#[rpc(server)]
+pub trait IndexerApi {
+	/// Indexed extrinsics of the best chain matching `filter`, in chain order.
+	#[rpc(name = "indexer_extrinsics")]
+	fn extrinsics(&self, filter: Filter) -> Result<Vec<IndexedExtrinsic>>;
+
+	/// Indexed events of the best chain matching `filter`, in chain order.
+	#[rpc(name = "indexer_events")]
+	fn events(&self, filter: Filter) -> Result<Vec<IndexedEvent>>;
+}
+
+/// Implementation of [`IndexerApi`].
+pub struct Indexer {
+	index: Arc<Index>,
+}
+
+impl Indexer {
+	/// Create new `Indexer` reading `index`.
+	pub fn new(index: Arc<Index>) -> Self {
+		Self { index }
+	}
+}
+
+impl IndexerApi for Indexer {
+	fn extrinsics(&self, filter: Filter) -> Result<Vec<IndexedExtrinsic>> {
+		self.index.extrinsics(&filter).map_err(query_error)
+	}
+
+	fn events(&self, filter: Filter) -> Result<Vec<IndexedEvent>> {
+		self.index.events(&filter).map_err(query_error)
+	}
+}
+
+fn query_error(e: String) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(1),
+		message: "Unable to query the index.".into(),
+		data: Some(e.into()),
+	}
+}
@@ -0,0 +1,146 @@
+//! Fee queries for light clients.
+
+use codec::{Decode, Encode};
+use futures::{future, FutureExt, TryFutureExt};
+use jsonrpc_core::{BoxFuture, Error as RpcError, ErrorCode};
+use jsonrpc_derive::rpc;
+use node_template_runtime::{opaque::Block, Balance, Hash};
+use pallet_transaction_payment_rpc_runtime_api::{FeeDetails, InclusionFee, RuntimeDispatchInfo};
+use sc_client_api::light::{future_header, Fetcher, RemoteBlockchain, RemoteCallRequest};
+use sp_blockchain::{Error as BlockChainError, HeaderBackend};
+use sp_core::Bytes;
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::sync::Arc;
+
+/// The `payment_*` methods of `pallet-transaction-payment-rpc`, served by a light client.
+///
+/// The full node version is synchronous, which does not work with remote calls.
+#[rpc(server)]
+pub trait LightTransactionPaymentApi<BlockHash> {
+	/// Weight, class and partial fee of an encoded extrinsic.
+	#[rpc(name = "payment_queryInfo")]
+	fn query_info(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<BlockHash>,
+	) -> BoxFuture<RuntimeDispatchInfo<Balance>>;
+
+	/// Breakdown of the fee of an encoded extrinsic.
+	#[rpc(name = "payment_queryFeeDetails")]
+	fn query_fee_details(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<BlockHash>,
+	) -> BoxFuture<FeeDetails<NumberOrHex>>;
+}
+
+/// Implementation of [`LightTransactionPaymentApi`] on top of remote calls.
+pub struct LightTransactionPayment<C, F> {
+	client: Arc<C>,
+	remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	fetcher: Arc<F>,
+}
+
+impl<C, F> LightTransactionPayment<C, F>
+where
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + 'static,
+{
+	/// Create new `LightTransactionPayment` with the given reference to the client.
+	pub fn new(
+		client: Arc<C>,
+		remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+		fetcher: Arc<F>,
+	) -> Self {
+		Self { client, remote_blockchain, fetcher }
+	}
+
+	/// Call `method` of the `TransactionPaymentApi` with the extrinsic and its length, at `at` or
+	/// at the best block, and decode the result.
+	fn remote_call<R: Decode + Send + 'static>(
+		&self,
+		method: &'static str,
+		encoded_xt: Bytes,
+		at: Option<Hash>,
+	) -> BoxFuture<R> {
+		let uxt = match <Block as BlockT>::Extrinsic::decode(&mut &*encoded_xt) {
+			Ok(uxt) => uxt,
+			Err(e) =>
+				return Box::new(jsonrpc_core::futures::future::err(RpcError {
+					code: ErrorCode::ServerError(1),
+					message: "Unable to decode extrinsic.".into(),
+					data: Some(format!("{:?}", e).into()),
+				})),
+		};
+		let call_data = (uxt, encoded_xt.len() as u32).encode();
+
+		let block = at.unwrap_or_else(|| self.client.info().best_hash);
+		let fetcher = self.fetcher.clone();
+		let header = future_header(&*self.remote_blockchain, &*fetcher, BlockId::Hash(block));
+		let result = header
+			.and_then(move |header| match header {
+				Some(header) => fetcher
+					.remote_call(RemoteCallRequest {
+						block,
+						header,
+						method: method.into(),
+						call_data,
+						retry_count: None,
+					})
+					.left_future(),
+				None => future::ready(Err(BlockChainError::UnknownBlock(format!("{}", block))))
+					.right_future(),
+			})
+			.map(move |result| {
+				let encoded = result.map_err(|e| RpcError {
+					code: ErrorCode::ServerError(1),
+					message: format!("Unable to call `{}` remotely.", method),
+					data: Some(format!("{:?}", e).into()),
+				})?;
+				R::decode(&mut &encoded[..]).map_err(|e| RpcError {
+					code: ErrorCode::ServerError(1),
+					message: format!("Unable to decode the result of `{}`.", method),
+					data: Some(format!("{:?}", e).into()),
+				})
+			});
+
+		Box::new(result.boxed().compat())
+	}
+}
+
+impl<C, F> LightTransactionPaymentApi<Hash> for LightTransactionPayment<C, F>
+where
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + 'static,
+{
+	fn query_info(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<Hash>,
+	) -> BoxFuture<RuntimeDispatchInfo<Balance>> {
+		self.remote_call("TransactionPaymentApi_query_info", encoded_xt, at)
+	}
+
+	fn query_fee_details(
+		&self,
+		encoded_xt: Bytes,
+		at: Option<Hash>,
+	) -> BoxFuture<FeeDetails<NumberOrHex>> {
+		let details = self.remote_call::<FeeDetails<Balance>>(
+			"TransactionPaymentApi_query_fee_details",
+			encoded_xt,
+			at,
+		);
+
+		// Same conversion as the full node, which does not report the tip either.
+		Box::new(jsonrpc_core::futures::Future::map(details, |details| FeeDetails {
+			inclusion_fee: details.inclusion_fee.map(|fee| InclusionFee {
+				base_fee: fee.base_fee.into(),
+				len_fee: fee.len_fee.into(),
+				adjusted_weight_fee: fee.adjusted_weight_fee.into(),
+			}),
+			tip: Default::default(),
+		}))
+	}
+}
@@ -0,0 +1,151 @@
+//! A collection of node-specific RPC methods.
+//! Substrate provides the `sc-rpc` crate, which defines the core RPC layer
+//! used by Substrate nodes. This file extends those RPC definitions with
+//! capabilities that are specific to this project's runtime configuration.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+
+use node_template_runtime::{
+	dry_run::DryRunApi as DryRunRuntimeApi, opaque::Block, AccountId, Balance, Hash, Index,
+};
+use sc_client_api::{
+	backend::{Backend, StateBackendFor},
+	light::{Fetcher, RemoteBlockchain},
+	BlockchainEvents,
+};
+pub use sc_rpc::SubscriptionTaskExecutor;
+pub use sc_rpc_api::DenyUnsafe;
+use sc_transaction_pool_api::TransactionPool;
+use sp_api::{ApiExt, Metadata, ProvideRuntimeApi};
+use sp_block_builder::BlockBuilder;
+use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+
+mod dispatch_error;
+mod dry_run;
+mod events;
+mod indexer;
+mod light_payment;
+mod txpool;
+
+pub use dispatch_error::{DispatchErrorApi, DispatchErrors};
+pub use dry_run::{DryRun, DryRunApi};
+pub use events::{Events, EventsApi};
+pub use indexer::{Indexer, IndexerApi};
+pub use light_payment::{LightTransactionPayment, LightTransactionPaymentApi};
+pub use txpool::{TxPool, TxPoolApi};
+
+/// Full client dependencies.
+pub struct FullDeps<C, P, B> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// The backend of the client.
+	pub backend: Arc<B>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Limits the transaction pool was configured with.
+	pub pool_options: sc_transaction_pool::Options,
+	/// Whether to deny unsafe calls
+	pub deny_unsafe: DenyUnsafe,
+	/// Executor of the subscriptions.
+	pub subscription_executor: SubscriptionTaskExecutor,
+	/// Read access to the chain index, if the indexer is enabled.
+	pub index: Option<Arc<crate::indexer::Index>>,
+}
+
+/// Instantiate all full RPC extensions.
+pub fn create_full<C, P, B>(deps: FullDeps<C, P, B>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
+where
+	B: Backend<Block> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: BlockchainEvents<Block>,
+	C: Send + Sync + 'static,
+	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
+	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: BlockBuilder<Block>,
+	C::Api: DryRunRuntimeApi<Block> + Metadata<Block>,
+	C::Api: ApiExt<Block, StateBackend = StateBackendFor<B, Block>>,
+	P: TransactionPool<Block = Block> + 'static,
+{
+	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use substrate_frame_rpc_system::{FullSystem, SystemApi};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	let FullDeps { client, backend, pool, pool_options, deny_unsafe, subscription_executor, index } =
+		deps;
+
+	io.extend_with(SystemApi::to_delegate(FullSystem::new(
+		client.clone(),
+		pool.clone(),
+		deny_unsafe,
+	)));
+
+	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
+
+	io.extend_with(TxPoolApi::to_delegate(TxPool::new(pool, pool_options, deny_unsafe)));
+
+	io.extend_with(DispatchErrorApi::to_delegate(DispatchErrors::new(client.clone())));
+
+	io.extend_with(EventsApi::to_delegate(Events::new(
+		client.clone(),
+		backend.clone(),
+		subscription_executor,
+	)));
+
+	io.extend_with(DryRunApi::to_delegate(DryRun::new(client, backend, deny_unsafe)));
+
+	if let Some(index) = index {
+		io.extend_with(IndexerApi::to_delegate(Indexer::new(index)));
+	}
+
+	// Extend this RPC with a custom API by using the following syntax.
+	// `YourRpcStruct` should have a reference to a client, which is needed
+	// to call into the runtime.
+	// `io.extend_with(YourRpcTrait::to_delegate(YourRpcStruct::new(ReferenceToClient, ...)));`
+
+	io
+}
+
+/// Light client extra dependencies.
+pub struct LightDeps<C, F, P> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Remote access to the blockchain (async).
+	pub remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+	/// Fetcher instance.
+	pub fetcher: Arc<F>,
+}
+
+/// Instantiate all light RPC extensions.
+///
+/// Light clients have no state, so runtime calls are executed by full nodes through the `fetcher`.
+pub fn create_light<C, P, F>(deps: LightDeps<C, F, P>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
+where
+	C: HeaderBackend<Block> + Send + Sync + 'static,
+	F: Fetcher<Block> + 'static,
+	P: TransactionPool + 'static,
+{
+	use substrate_frame_rpc_system::{LightSystem, SystemApi};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	let LightDeps { client, pool, remote_blockchain, fetcher } = deps;
+
+	io.extend_with(SystemApi::<Hash, AccountId, Index>::to_delegate(LightSystem::new(
+		client.clone(),
+		remote_blockchain.clone(),
+		fetcher.clone(),
+		pool,
+	)));
+
+	io.extend_with(LightTransactionPaymentApi::to_delegate(LightTransactionPayment::new(
+		client,
+		remote_blockchain,
+		fetcher,
+	)));
+
+	io
+}
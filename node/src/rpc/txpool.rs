@@ -0,0 +1,185 @@
+//! Inspection and management of the transaction pool.
+//!
+//! Every method of the `txpool_` namespace is unsafe, as it exposes and alters the local pool.
+
+use codec::{Decode, Encode};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use node_template_runtime::{opaque::Block, Address, Index, UncheckedExtrinsic};
+use sc_rpc_api::DenyUnsafe;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool, TxHash};
+use serde::{Deserialize, Serialize};
+use sp_core::{crypto::Ss58Codec, Bytes};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Key under which unsigned transactions are grouped.
+const UNSIGNED: &str = "unsigned";
+
+/// Transactions of the pool, grouped by sender and ordered by nonce.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PoolContent<Hash> {
+	/// Transactions that can be included in the next block.
+	pub ready: BTreeMap<String, Vec<PoolTransaction<Hash>>>,
+	/// Transactions waiting for the tags they require, e.g. an earlier nonce.
+	pub future: BTreeMap<String, Vec<PoolTransaction<Hash>>>,
+}
+
+/// A transaction of the pool.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolTransaction<Hash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// Nonce of a signed transaction.
+	pub nonce: Option<Index>,
+	/// Priority given by `validate_transaction`.
+	pub priority: u64,
+	/// Number of blocks the transaction stays valid for.
+	pub longevity: u64,
+	/// Tags that must be provided by other transactions first.
+	pub requires: Vec<Bytes>,
+	/// Tags provided by the transaction.
+	pub provides: Vec<Bytes>,
+	/// Whether the transaction is gossiped to other nodes.
+	pub propagate: bool,
+}
+
+/// Occupation and limits of the pool.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PoolStatus {
+	/// The ready queue.
+	pub ready: QueueStatus,
+	/// The future queue.
+	pub future: QueueStatus,
+}
+
+/// Occupation and limits of a queue of the pool.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStatus {
+	/// Number of transactions.
+	pub count: usize,
+	/// Total size of the transactions.
+	pub bytes: usize,
+	/// Maximum number of transactions.
+	pub max_count: usize,
+	/// Maximum total size of the transactions.
+	pub max_bytes: usize,
+}
+
+/// Transaction pool RPC methods.
+#[rpc(server)]
+pub trait TxPoolApi<Hash> {
+	/// Ready and future transactions, grouped by sender.
+	#[rpc(name = "txpool_content")]
+	fn content(&self) -> Result<PoolContent<Hash>>;
+
+	/// Number and size of the transactions in the pool, with its limits.
+	#[rpc(name = "txpool_status")]
+	fn status(&self) -> Result<PoolStatus>;
+
+	/// Remove a transaction and the transactions depending on it from the pool.
+	///
+	/// The removed transactions are banned for `--tx-ban-seconds`. Returns whether the
+	/// transaction was in the pool.
+	#[rpc(name = "txpool_remove")]
+	fn remove(&self, hash: Hash) -> Result<bool>;
+}
+
+/// Implementation of [`TxPoolApi`].
+pub struct TxPool<P> {
+	pool: Arc<P>,
+	limits: sc_transaction_pool::Options,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<P> TxPool<P> {
+	/// Create new `TxPool` for the given pool and its configured `limits`.
+	pub fn new(
+		pool: Arc<P>,
+		limits: sc_transaction_pool::Options,
+		deny_unsafe: DenyUnsafe,
+	) -> Self {
+		Self { pool, limits, deny_unsafe }
+	}
+}
+
+impl<P> TxPoolApi<TxHash<P>> for TxPool<P>
+where
+	P: TransactionPool<Block = Block> + 'static,
+{
+	fn content(&self) -> Result<PoolContent<TxHash<P>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let mut content = PoolContent { ready: BTreeMap::new(), future: BTreeMap::new() };
+		for tx in self.pool.ready() {
+			let (sender, tx) = describe(&*tx);
+			content.ready.entry(sender).or_insert_with(Vec::new).push(tx);
+		}
+		for tx in self.pool.futures() {
+			let (sender, tx) = describe(&tx);
+			content.future.entry(sender).or_insert_with(Vec::new).push(tx);
+		}
+		for txs in content.ready.values_mut().chain(content.future.values_mut()) {
+			txs.sort_by_key(|tx| tx.nonce);
+		}
+
+		Ok(content)
+	}
+
+	fn status(&self) -> Result<PoolStatus> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let status = self.pool.status();
+		Ok(PoolStatus {
+			ready: QueueStatus {
+				count: status.ready,
+				bytes: status.ready_bytes,
+				max_count: self.limits.ready.count,
+				max_bytes: self.limits.ready.total_bytes,
+			},
+			future: QueueStatus {
+				count: status.future,
+				bytes: status.future_bytes,
+				max_count: self.limits.future.count,
+				max_bytes: self.limits.future.total_bytes,
+			},
+		})
+	}
+
+	fn remove(&self, hash: TxHash<P>) -> Result<bool> {
+		self.deny_unsafe.check_if_safe()?;
+
+		Ok(!self.pool.remove_invalid(&[hash]).is_empty())
+	}
+}
+
+/// Sender and description of a transaction of the pool.
+fn describe<T, Hash>(tx: &T) -> (String, PoolTransaction<Hash>)
+where
+	T: InPoolTransaction<
+		Transaction = <Block as sp_runtime::traits::Block>::Extrinsic,
+		Hash = Hash,
+	>,
+	Hash: Clone,
+{
+	let signature = UncheckedExtrinsic::decode(&mut &tx.data().encode()[..])
+		.ok()
+		.and_then(|xt| xt.signature);
+	let (sender, nonce) = match signature {
+		Some((Address::Id(who), _, extra)) => (who.to_ss58check(), Some(extra.4 .0)),
+		Some((address, _, extra)) => (format!("{:?}", address), Some(extra.4 .0)),
+		None => (UNSIGNED.into(), None),
+	};
+
+	let tx = PoolTransaction {
+		hash: tx.hash().clone(),
+		nonce,
+		priority: *tx.priority(),
+		longevity: *tx.longevity(),
+		requires: tx.requires().iter().cloned().map(Bytes).collect(),
+		provides: tx.provides().iter().cloned().map(Bytes).collect(),
+		propagate: tx.is_propagable(),
+	};
+	(sender, tx)
+}
@@ -1,16 +1,18 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use node_template_runtime::{self, opaque::Block, RuntimeApi};
+use node_template_runtime::{self, opaque::Block, BlockNumber, RuntimeApi};
 use sc_client_api::{ExecutorProvider, RemoteBackend};
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
+use sc_consensus_slots::BackoffAuthoringOnFinalizedHeadLagging;
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sc_finality_grandpa::SharedVoterState;
 use sc_keystore::LocalKeystore;
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
+use sc_service::{error::Error as ServiceError, ChainType, Configuration, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sp_consensus::SlotData;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
+use sp_runtime::generic::BlockId;
 use std::{sync::Arc, time::Duration};
 
 // Our native executor instance.
@@ -21,8 +23,35 @@ native_executor_instance!(
 	frame_benchmarking::benchmarking::HostFunctions,
 );
 
-type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
-type FullBackend = sc_service::TFullBackend<Block>;
+/// Configuration of the full node that is not part of the `sc-service` [`Configuration`].
+#[derive(Clone, Debug, Default)]
+pub struct ExtraConfig {
+	/// Run the GRANDPA observer instead of the voter. Only allowed on non-authority nodes.
+	pub grandpa_observer: bool,
+	/// Slow down authoring when finality lags, see [`BackoffConfig`].
+	pub backoff_authoring: Option<BackoffConfig>,
+	/// Serve the probes of [`crate::health`].
+	pub health: Option<crate::health::HealthConfig>,
+	/// Save the transaction pool on shutdown and restore it on start, in a file of at most this
+	/// many bytes. See [`crate::pool_persistence`].
+	pub persist_pool: Option<usize>,
+	/// Index the chain in the SQLite database at this path. See [`crate::indexer`].
+	pub indexer: Option<std::path::PathBuf>,
+}
+
+/// Parameters of the `BackoffAuthoringOnFinalizedHeadLagging` strategy.
+#[derive(Clone, Copy, Debug)]
+pub struct BackoffConfig {
+	/// Number of unfinalized blocks authored before backing off.
+	pub unfinalized_slack: BlockNumber,
+	/// Maximum number of slots skipped between two blocks.
+	pub max_interval: BlockNumber,
+}
+
+/// Client of a full node.
+pub type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
+/// Database backend of a full node.
+pub type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
 pub fn new_partial(
@@ -138,7 +167,14 @@ fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_full(
+	mut config: Configuration,
+	extra_config: ExtraConfig,
+) -> Result<TaskManager, ServiceError> {
+	if extra_config.grandpa_observer && config.role.is_authority() {
+		return Err(ServiceError::Other("Authorities cannot run the GRANDPA observer.".into()))
+	}
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -161,6 +197,41 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		};
 	}
 
+	if let Some(max_size) = extra_config.persist_pool {
+		let path = match &config.base_path {
+			Some(base_path) => base_path
+				.config_dir(config.chain_spec.id())
+				.join(crate::pool_persistence::FILE_NAME),
+			None =>
+				return Err(ServiceError::Other(
+					"Persisting the transaction pool requires a base path.".into(),
+				)),
+		};
+		let extrinsics = crate::pool_persistence::load(&path, max_size);
+		if !extrinsics.is_empty() {
+			let best = BlockId::Hash(client.chain_info().best_hash);
+			task_manager.spawn_handle().spawn(
+				"txpool-restore",
+				crate::pool_persistence::restore(transaction_pool.clone(), best, extrinsics),
+			);
+		}
+		task_manager.keep_alive(crate::pool_persistence::SaveOnDrop::new(
+			transaction_pool.clone(),
+			path,
+			max_size,
+		));
+	}
+
+	let index = match &extra_config.indexer {
+		Some(path) => {
+			let indexer = crate::indexer::Indexer::open(path, client.clone(), backend.clone())
+				.map_err(ServiceError::Other)?;
+			task_manager.spawn_handle().spawn_blocking("indexer", indexer.run());
+			Some(Arc::new(crate::indexer::Index::open(path).map_err(ServiceError::Other)?))
+		},
+		None => None,
+	};
+
 	config.network.extra_sets.push(sc_finality_grandpa::grandpa_peers_set_config());
 
 	let (network, system_rpc_tx, network_starter) =
@@ -183,20 +254,57 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		);
 	}
 
+	if let Some(health) = extra_config.health {
+		let listener = std::net::TcpListener::bind(health.addr).map_err(|e| {
+			ServiceError::Other(format!(
+				"Failed to bind the health server to {}: {}",
+				health.addr, e
+			))
+		})?;
+		listener.set_nonblocking(true)?;
+		let probes = crate::health::Probes {
+			config: health,
+			require_peers: config.chain_spec.chain_type() != ChainType::Development,
+			client: client.clone(),
+			network: network.clone(),
+			keystore: keystore_container.sync_keystore(),
+		};
+		task_manager.spawn_handle().spawn("health-server", probes.run(listener));
+	}
+
 	let role = config.role.clone();
 	let force_authoring = config.force_authoring;
-	let backoff_authoring_blocks: Option<()> = None;
+	let backoff_authoring_blocks = extra_config
+		.backoff_authoring
+		.map(|backoff| {
+			let strategy = BackoffAuthoringOnFinalizedHeadLagging {
+				unfinalized_slack: backoff.unfinalized_slack,
+				max_interval: backoff.max_interval,
+				..Default::default()
+			};
+			crate::metrics::MeteredBackoff::new(strategy, config.prometheus_registry())
+		})
+		.transpose()?;
 	let name = config.network.node_name.clone();
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
 	let rpc_extensions_builder = {
 		let client = client.clone();
+		let backend = backend.clone();
 		let pool = transaction_pool.clone();
+		let pool_options = config.transaction_pool.clone();
 
-		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				backend: backend.clone(),
+				pool: pool.clone(),
+				pool_options: pool_options.clone(),
+				deny_unsafe,
+				subscription_executor,
+				index: index.clone(),
+			};
 
 			crate::rpc::create_full(deps)
 		})
@@ -225,6 +333,18 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			prometheus_registry.as_ref(),
 			telemetry.as_ref().map(|x| x.handle()),
 		);
+		let proposer_factory = crate::metrics::MeteredProposerFactory::new(
+			proposer_factory,
+			prometheus_registry.as_ref(),
+		)?;
+
+		if let Some(registry) = prometheus_registry.as_ref() {
+			let metrics = crate::metrics::SlotMetrics::register(registry)?;
+			task_manager.spawn_handle().spawn(
+				"slot-metrics",
+				metrics.run(client.clone(), keystore_container.sync_keystore()),
+			);
+		}
 
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
@@ -284,28 +404,47 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	};
 
 	if enable_grandpa {
-		// start the full GRANDPA voter
-		// NOTE: non-authorities could run the GRANDPA observer protocol, but at
-		// this point the full voter should provide better guarantees of block
-		// and vote data availability than the observer. The observer has not
-		// been tested extensively yet and having most nodes in a network run it
-		// could lead to finality stalls.
-		let grandpa_config = sc_finality_grandpa::GrandpaParams {
-			config: grandpa_config,
-			link: grandpa_link,
-			network,
-			voting_rule: sc_finality_grandpa::VotingRulesBuilder::default().build(),
-			prometheus_registry,
-			shared_voter_state: SharedVoterState::empty(),
-			telemetry: telemetry.as_ref().map(|x| x.handle()),
+		let mode = if extra_config.grandpa_observer {
+			crate::metrics::GrandpaMode::Observer
+		} else {
+			crate::metrics::GrandpaMode::Voter
 		};
-
-		// the GRANDPA voter task is considered infallible, i.e.
-		// if it fails we take down the service with it.
-		task_manager.spawn_essential_handle().spawn_blocking(
-			"grandpa-voter",
-			sc_finality_grandpa::run_grandpa_voter(grandpa_config)?,
-		);
+		if let Some(registry) = prometheus_registry.as_ref() {
+			let metrics = crate::metrics::FinalityLagMetrics::register(registry, mode)?;
+			task_manager
+				.spawn_handle()
+				.spawn("finality-lag-metrics", metrics.run(client.clone()));
+		}
+
+		if extra_config.grandpa_observer {
+			// follow finality from the commit messages of the authorities, without taking
+			// part in vote gossip.
+			task_manager.spawn_essential_handle().spawn_blocking(
+				"grandpa-observer",
+				sc_finality_grandpa::run_grandpa_observer(grandpa_config, grandpa_link, network)?,
+			);
+		} else {
+			// start the full GRANDPA voter
+			// NOTE: the full voter provides better guarantees of block and vote data
+			// availability than the observer, which is why non-authorities only run the
+			// observer when asked to with `--grandpa-observer`.
+			let grandpa_config = sc_finality_grandpa::GrandpaParams {
+				config: grandpa_config,
+				link: grandpa_link,
+				network,
+				voting_rule: sc_finality_grandpa::VotingRulesBuilder::default().build(),
+				prometheus_registry,
+				shared_voter_state: SharedVoterState::empty(),
+				telemetry: telemetry.as_ref().map(|x| x.handle()),
+			};
+
+			// the GRANDPA voter task is considered infallible, i.e.
+			// if it fails we take down the service with it.
+			task_manager.spawn_essential_handle().spawn_blocking(
+				"grandpa-voter",
+				sc_finality_grandpa::run_grandpa_voter(grandpa_config)?,
+			);
+		}
 	}
 
 	network_starter.start_network();
@@ -420,12 +559,20 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		);
 	}
 
+	let light_deps = crate::rpc::LightDeps {
+		remote_blockchain: backend.remote_blockchain(),
+		fetcher: on_demand.clone(),
+		client: client.clone(),
+		pool: transaction_pool.clone(),
+	};
+	let rpc_extensions = crate::rpc::create_light(light_deps);
+
 	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
 		remote_blockchain: Some(backend.remote_blockchain()),
 		transaction_pool,
 		task_manager: &mut task_manager,
 		on_demand: Some(on_demand),
-		rpc_extensions_builder: Box::new(|_, _| ()),
+		rpc_extensions_builder: Box::new(sc_service::NoopRpcExtensionBuilder(rpc_extensions)),
 		config,
 		client,
 		keystore: keystore_container.sync_keystore(),
@@ -0,0 +1,48 @@
+#![cfg(unix)]
+
+use std::process::Command;
+
+#[test]
+fn benchmark_block_reports_blocks_against_the_slot() {
+	let output = Command::new(env!("CARGO_BIN_EXE_node-template"))
+		.args(&["benchmark-block", "--dev", "--execution", "Wasm"])
+		.args(&["--blocks", "2", "--max-extrinsics", "50"])
+		.output()
+		.expect("failed to run node-template");
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+
+	assert!(stdout.contains("Balances::transfer #1: 50 extrinsics"), "{}", stdout);
+	assert!(stdout.contains("Balances::transfer #2: 50 extrinsics"), "{}", stdout);
+	assert!(stdout.contains("TemplateModule::do_something #3: 50 extrinsics"), "{}", stdout);
+	assert!(stdout.contains("TemplateModule::do_something #4: 50 extrinsics"), "{}", stdout);
+	assert!(stdout.contains("Balances::transfer: blocks stay within the 6000.0 ms slot"));
+	assert!(stdout.contains("TemplateModule::do_something: blocks stay within the 6000.0 ms slot"));
+}
+
+#[test]
+fn benchmark_block_fills_blocks_up_to_the_weight_limit() {
+	let output = Command::new(env!("CARGO_BIN_EXE_node-template"))
+		.args(&["benchmark-block", "--dev", "--execution", "Wasm"])
+		.args(&["--blocks", "1", "--extrinsic", "transfer"])
+		.output()
+		.expect("failed to run node-template");
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	let stderr = String::from_utf8_lossy(&output.stderr);
+
+	// Blocks are filled until `CheckWeight` rejects a transfer, far beyond a handful of them.
+	let line = stdout
+		.lines()
+		.find(|line| line.starts_with("Balances::transfer #1: "))
+		.unwrap_or_else(|| panic!("block #1 is not reported: {}{}", stdout, stderr));
+	let extrinsics: usize = line["Balances::transfer #1: ".len()..]
+		.split(' ')
+		.next()
+		.and_then(|count| count.parse().ok())
+		.unwrap_or_else(|| panic!("no extrinsic count in `{}`", line));
+	assert!(extrinsics > 1000, "{}", line);
+
+	// Whether a full block fits depends on the hardware, the verdict must match the exit status.
+	let exceeds = stdout.contains("Balances::transfer: blocks EXCEED their budget");
+	assert_eq!(output.status.success(), !exceeds, "{}{}", stdout, stderr);
+}
@@ -0,0 +1,168 @@
+#![cfg(unix)]
+
+use node_template_runtime::{pallet_template, Call, Event};
+use serde_json::{json, Value};
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::AccountKeyring;
+use std::{path::Path, time::Duration};
+use tempfile::tempdir;
+
+pub mod common;
+
+#[tokio::test]
+async fn purge_chain_removes_the_database() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions { key: Some("alice"), force_authoring: true, ..Default::default() },
+	);
+	common::wait_for_best(&node.client().await, 2, Duration::from_secs(60)).await;
+	assert!(node.stop().success());
+
+	let db_path = base_path.path().join("chains/local_testnet/db");
+	assert!(db_path.exists());
+
+	let base_path_arg = base_path.path().to_str().unwrap();
+	let status =
+		common::run_cmd(&["purge-chain", "--chain", "local", "--base-path", base_path_arg, "-y"]);
+	assert!(status.success());
+
+	assert!(!db_path.exists());
+	// The keystore and network keys are kept.
+	assert!(base_path.path().join("chains/local_testnet").exists());
+}
+
+/// Best block of the database in `base_path`, read from a node that does not author.
+async fn best_number_of(base_path: &Path) -> u32 {
+	let node = common::Node::start(base_path, Default::default());
+	let best = common::best_number(&node.client().await).await;
+	assert!(node.stop().success());
+	best
+}
+
+#[tokio::test]
+async fn revert_removes_unfinalized_blocks() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	// `alice` alone only authors when forced to, and holds half of the GRANDPA votes of
+	// `--chain local`: nothing is finalized, everything is revertable.
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions { key: Some("alice"), force_authoring: true, ..Default::default() },
+	);
+	common::wait_for_best(&node.client().await, 3, Duration::from_secs(60)).await;
+	assert!(node.stop().success());
+
+	let best = best_number_of(base_path.path()).await;
+
+	let base_path_arg = base_path.path().to_str().unwrap();
+	let status =
+		common::run_cmd(&["revert", "--chain", "local", "--base-path", base_path_arg, "2"]);
+	assert!(status.success());
+
+	assert_eq!(best_number_of(base_path.path()).await, best - 2);
+}
+
+#[tokio::test]
+async fn network_resumes_after_restart() {
+	let network = common::Network::start(&["alice", "bob"]);
+	let client = network.nodes[0].client().await;
+	common::wait_for_finalized(&client, 3, Duration::from_secs(120)).await;
+	let finalized = common::finalized_number(&client).await;
+	drop(client);
+
+	let network = network.restart(&["alice", "bob"]);
+
+	for node in &network.nodes {
+		let client = node.client().await;
+		// The previous chain is still there...
+		assert!(common::finalized_number(&client).await >= finalized);
+		// ...and is extended and finalized further.
+		common::wait_for_finalized(&client, finalized + 2, Duration::from_secs(120)).await;
+	}
+}
+
+/// Blocks exported with `export-blocks --decoded` from the database in `base_path`.
+fn decoded_blocks(base_path: &Path, output: &Path) -> Vec<Value> {
+	let base_path_arg = base_path.to_str().unwrap();
+	let output_arg = output.to_str().unwrap();
+	let args = [
+		"export-blocks",
+		"--chain",
+		"local",
+		"--base-path",
+		base_path_arg,
+		"--decoded",
+		output_arg,
+	];
+	assert!(common::run_cmd(&args).success());
+	std::fs::read_to_string(output)
+		.expect("export was written")
+		.lines()
+		.map(|line| serde_json::from_str(line).expect("export is JSON lines"))
+		.collect()
+}
+
+#[tokio::test]
+async fn decoded_export_round_trips_through_import() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions { key: Some("alice"), force_authoring: true, ..Default::default() },
+	);
+	let client = node.client().await;
+	common::wait_for_best(&client, 1, Duration::from_secs(60)).await;
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	common::submit(&client, &AccountKeyring::Alice.pair(), call).await;
+	let stored = common::wait_for_event(
+		&client,
+		|event| matches!(event, Event::TemplateModule(pallet_template::Event::SomethingStored(..))),
+		Duration::from_secs(60),
+	)
+	.await;
+	drop(client);
+	assert!(node.stop().success());
+
+	let exported = base_path.path().join("blocks.jsonl");
+	let blocks = decoded_blocks(base_path.path(), &exported);
+	let block = blocks
+		.iter()
+		.find(|block| block["hash"] == json!(stored))
+		.expect("block of the extrinsic is exported");
+	let xt = block["extrinsics"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.find(|xt| xt["call"]["pallet"] == json!("TemplateModule"))
+		.expect("extrinsic is decoded");
+	assert_eq!(xt["call"]["name"], json!("do_something"));
+	assert_eq!(xt["call"]["args"], json!({ "something": 42 }));
+	assert_eq!(xt["signer"], json!(AccountKeyring::Alice.to_account_id().to_ss58check()));
+	assert_eq!(xt["nonce"], json!(0));
+	assert_eq!(xt["tip"], json!("0"));
+	assert_eq!(xt["success"], json!(true));
+	assert!(block["events"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.any(|event| event["pallet"] == json!("TemplateModule") &&
+			event["name"] == json!("SomethingStored") &&
+			event["extrinsic"] == xt["index"]));
+
+	let imported_path = tempdir().expect("could not create a temp dir");
+	let imported_path_arg = imported_path.path().to_str().unwrap();
+	let exported_arg = exported.to_str().unwrap();
+	let args = [
+		"import-blocks",
+		"--chain",
+		"local",
+		"--base-path",
+		imported_path_arg,
+		"--decoded",
+		exported_arg,
+	];
+	assert!(common::run_cmd(&args).success());
+
+	let reexported = imported_path.path().join("blocks.jsonl");
+	assert_eq!(decoded_blocks(imported_path.path(), &reexported), blocks);
+}
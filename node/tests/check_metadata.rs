@@ -0,0 +1,150 @@
+#![cfg(unix)]
+
+use codec::{Decode, Encode};
+use frame_metadata::{
+	DecodeDifferent, ModuleMetadata, RuntimeMetadata, RuntimeMetadataPrefixed, StorageEntryType,
+};
+use sp_version::RuntimeVersion;
+use std::{path::Path, process::Command};
+use tempfile::tempdir;
+
+pub mod common;
+
+#[test]
+fn unchanged_metadata_passes_against_its_own_snapshot() {
+	let dir = tempdir().expect("could not create a temp dir");
+	let snapshot = dir.path().join("metadata.snap");
+	let snapshot_arg = snapshot.to_str().unwrap();
+
+	assert!(common::run_cmd(&["check-metadata", "--save", snapshot_arg]).success());
+	assert!(common::run_cmd(&["check-metadata", "--baseline", snapshot_arg]).success());
+}
+
+#[test]
+fn changed_metadata_requires_a_version_bump() {
+	let dir = tempdir().expect("could not create a temp dir");
+	let snapshot = dir.path().join("metadata.snap");
+
+	// Pretend the baseline had one pallet less: the current runtime adds it without bumping
+	// `spec_version`.
+	let mut removed = String::new();
+	save_edited_snapshot(&snapshot, |_, pallets| {
+		let mut pallet = pallets.pop().expect("the runtime has pallets");
+		removed = decoded(&mut pallet.name).clone();
+	});
+
+	let (success, stdout, stderr) = check_against(&snapshot);
+	assert!(!success);
+	assert!(stdout.contains(&format!("+ pallet {}", removed)), "{}", stdout);
+	assert!(stdout.contains("1 change(s), 0 of which break transactions."), "{}", stdout);
+	assert!(stderr.contains("`spec_version` must be bumped"), "{}", stderr);
+}
+
+#[test]
+fn removed_call_requires_a_transaction_version_bump() {
+	let dir = tempdir().expect("could not create a temp dir");
+	let snapshot = dir.path().join("metadata.snap");
+
+	// Pretend the baseline had one more call and a lower `spec_version`: the current runtime
+	// removes the call, which invalidates transactions, but only bumps `spec_version`.
+	save_edited_snapshot(&snapshot, |version, pallets| {
+		version.spec_version -= 1;
+		let calls = decoded(template_pallet(pallets).calls.as_mut().expect("pallet has calls"));
+		let mut call = calls[0].clone();
+		call.name = DecodeDifferent::Decoded("do_something_else".into());
+		calls.push(call);
+	});
+
+	let (success, stdout, stderr) = check_against(&snapshot);
+	assert!(!success);
+	assert!(
+		stdout.contains("- call TemplateModule::do_something_else(something: u32)"),
+		"{}",
+		stdout
+	);
+	assert!(stdout.contains("1 change(s), 1 of which break transactions."), "{}", stdout);
+	assert!(stderr.contains("`transaction_version` must be bumped"), "{}", stderr);
+}
+
+#[test]
+fn changed_call_and_storage_item_are_reported() {
+	let dir = tempdir().expect("could not create a temp dir");
+	let snapshot = dir.path().join("metadata.snap");
+
+	// Pretend `do_something` used to take a `u64` and `Something` to store one.
+	save_edited_snapshot(&snapshot, |_, pallets| {
+		let pallet = template_pallet(pallets);
+		let call = &mut decoded(pallet.calls.as_mut().expect("pallet has calls"))[0];
+		decoded(&mut call.arguments)[0].ty = DecodeDifferent::Decoded("u64".into());
+		let storage = decoded(pallet.storage.as_mut().expect("pallet has storage"));
+		decoded(&mut storage.entries)[0].ty =
+			StorageEntryType::Plain(DecodeDifferent::Decoded("u64".into()));
+	});
+
+	let (success, stdout, stderr) = check_against(&snapshot);
+	assert!(!success);
+	assert!(
+		stdout.contains(
+			"~ call TemplateModule::do_something(something: u64) -> do_something(something: u32)"
+		),
+		"{}",
+		stdout
+	);
+	assert!(
+		stdout.contains(
+			"~ storage TemplateModule::Something: Optional [] -> u64 (default 0x00) -> \
+			 Something: Optional [] -> u32 (default 0x00)"
+		),
+		"{}",
+		stdout
+	);
+	// Only the call is part of transactions.
+	assert!(stdout.contains("2 change(s), 1 of which break transactions."), "{}", stdout);
+	assert!(stderr.contains("`spec_version` must be bumped"), "{}", stderr);
+}
+
+/// Save a snapshot of this runtime to `snapshot`, with its version and pallets edited by `edit`.
+fn save_edited_snapshot(
+	snapshot: &Path,
+	edit: impl FnOnce(&mut RuntimeVersion, &mut Vec<ModuleMetadata>),
+) {
+	assert!(common::run_cmd(&["check-metadata", "--save", snapshot.to_str().unwrap()]).success());
+
+	let raw = std::fs::read(snapshot).unwrap();
+	let (mut version, metadata) = <(RuntimeVersion, Vec<u8>)>::decode(&mut &raw[..]).unwrap();
+	let mut prefixed = RuntimeMetadataPrefixed::decode(&mut &metadata[..]).unwrap();
+	match &mut prefixed.1 {
+		RuntimeMetadata::V13(metadata) => edit(&mut version, decoded(&mut metadata.modules)),
+		_ => panic!("metadata V13 expected"),
+	}
+	std::fs::write(snapshot, (&version, prefixed.encode()).encode()).unwrap();
+}
+
+/// Run `check-metadata` against `snapshot`, returns whether it passed, its stdout and its stderr.
+fn check_against(snapshot: &Path) -> (bool, String, String) {
+	let output = Command::new(env!("CARGO_BIN_EXE_node-template"))
+		.args(&["check-metadata", "--baseline", snapshot.to_str().unwrap()])
+		.output()
+		.expect("failed to run node-template");
+	(
+		output.status.success(),
+		String::from_utf8_lossy(&output.stdout).into_owned(),
+		String::from_utf8_lossy(&output.stderr).into_owned(),
+	)
+}
+
+fn template_pallet(pallets: &mut [ModuleMetadata]) -> &mut ModuleMetadata {
+	let index = pallets
+		.iter_mut()
+		.position(|pallet| *decoded(&mut pallet.name) == "TemplateModule")
+		.expect("the runtime has the template pallet");
+	&mut pallets[index]
+}
+
+/// Metadata decoded from SCALE is always `Decoded`.
+fn decoded<B: 'static, O: 'static>(value: &mut DecodeDifferent<B, O>) -> &mut O {
+	match value {
+		DecodeDifferent::Decoded(value) => value,
+		DecodeDifferent::Encode(_) => unreachable!("decoded metadata is always `Decoded`"),
+	}
+}
@@ -0,0 +1,399 @@
+//! Helpers to run `node-template` processes and talk to them over RPC.
+
+#![cfg(unix)]
+#![allow(dead_code)]
+
+use codec::{Decode, Encode};
+use jsonrpsee_ws_client::{
+	types::{traits::Client, v2::params::JsonRpcParams},
+	WsClient, WsClientBuilder,
+};
+use nix::{
+	sys::signal::{kill, Signal::SIGINT},
+	unistd::Pid,
+};
+use node_template_runtime::{
+	Address, Call, Event, Hash, Header, Index, Runtime, SignedExtra, UncheckedExtrinsic, VERSION,
+};
+use serde_json::{json, Value};
+use sp_core::{hashing::twox_128, sr25519, Bytes, Pair};
+use sp_runtime::{generic::Era, traits::Header as _};
+use std::{
+	convert::TryInto,
+	fs::File,
+	io::{Read, Write},
+	net::{TcpListener, TcpStream},
+	path::{Path, PathBuf},
+	process::{Child, Command, ExitStatus, Stdio},
+	time::Duration,
+};
+use tokio::time::{sleep, Instant};
+
+/// Peer id derived from the `--node-key` given to the first node of a network.
+const BOOTNODE_PEER_ID: &str = "12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp";
+const BOOTNODE_KEY: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+
+/// Events stored by `frame_system` for a block.
+pub type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// Run a `node-template` subcommand to completion.
+pub fn run_cmd(args: &[&str]) -> ExitStatus {
+	Command::new(env!("CARGO_BIN_EXE_node-template"))
+		.args(args)
+		.stdout(Stdio::null())
+		.status()
+		.expect("failed to run node-template")
+}
+
+/// Wait for the given `child` the given number of `secs`.
+///
+/// Returns the `Some(exit status)` or `None` if the process did not finish in the given time.
+pub fn wait_for(child: &mut Child, secs: u64) -> Option<ExitStatus> {
+	for _ in 0..secs * 10 {
+		match child.try_wait().expect("failed to poll node-template") {
+			Some(status) => return Some(status),
+			None => std::thread::sleep(Duration::from_millis(100)),
+		}
+	}
+	None
+}
+
+/// A port nothing listens on yet.
+pub fn free_port() -> u16 {
+	TcpListener::bind("127.0.0.1:0")
+		.and_then(|listener| listener.local_addr())
+		.expect("no free port available")
+		.port()
+}
+
+/// A `node-template` process running on `--chain local`.
+///
+/// The process is killed when this is dropped, use [`Node::stop`] for a graceful shutdown.
+pub struct Node {
+	child: Child,
+	/// Database and keystore directory of the node.
+	pub base_path: PathBuf,
+	/// Port of the libp2p listener.
+	pub p2p_port: u16,
+	/// Port of the WebSocket RPC server.
+	pub ws_port: u16,
+	/// Port of the Prometheus exporter.
+	pub prometheus_port: u16,
+}
+
+/// Options for starting a [`Node`].
+#[derive(Clone, Default)]
+pub struct NodeOptions<'a> {
+	/// Well-known key the node authors with, e.g. `alice`. `None` for a non-authority node.
+	pub key: Option<&'a str>,
+	/// Author blocks even without peers. An authority of `--chain local` started alone otherwise
+	/// considers itself offline and skips every slot.
+	pub force_authoring: bool,
+	/// Multiaddress of a node to connect to.
+	pub bootnode: Option<String>,
+	/// Make the node's peer id predictable, so that others can use it as a bootnode.
+	pub is_bootnode: bool,
+	/// Additional command line arguments.
+	pub extra_args: Vec<&'a str>,
+}
+
+impl Node {
+	/// Start a node storing its data in `base_path`.
+	pub fn start(base_path: &Path, options: NodeOptions) -> Node {
+		let p2p_port = free_port();
+		let ws_port = free_port();
+		let prometheus_port = free_port();
+		let log = File::create(base_path.join(format!("node-{}.log", ws_port)))
+			.expect("failed to create log file");
+
+		let mut cmd = Command::new(env!("CARGO_BIN_EXE_node-template"));
+		cmd.args(&["--chain", "local", "--no-mdns", "--no-telemetry"])
+			.arg("--base-path")
+			.arg(base_path)
+			.args(&["--port", &p2p_port.to_string()])
+			.args(&["--ws-port", &ws_port.to_string()])
+			.args(&["--prometheus-port", &prometheus_port.to_string()])
+			.args(&["--rpc-port", &free_port().to_string()])
+			.args(&["--rpc-methods", "unsafe"]);
+		if let Some(key) = options.key {
+			cmd.arg(format!("--{}", key));
+		}
+		if options.force_authoring {
+			cmd.arg("--force-authoring");
+		}
+		if let Some(bootnode) = &options.bootnode {
+			cmd.args(&["--bootnodes", bootnode]);
+		}
+		if options.is_bootnode {
+			cmd.args(&["--node-key", BOOTNODE_KEY]);
+		}
+		cmd.args(&options.extra_args);
+
+		let child = cmd
+			.stdout(Stdio::null())
+			.stderr(log)
+			.spawn()
+			.expect("failed to start node-template");
+
+		Node { child, base_path: base_path.to_path_buf(), p2p_port, ws_port, prometheus_port }
+	}
+
+	/// Multiaddress other nodes can use to connect to this one, if started as a bootnode.
+	pub fn bootnode_address(&self) -> String {
+		format!("/ip4/127.0.0.1/tcp/{}/p2p/{}", self.p2p_port, BOOTNODE_PEER_ID)
+	}
+
+	/// Connect to the WebSocket RPC server of the node, waiting for it to come up.
+	pub async fn client(&self) -> WsClient {
+		let url = format!("ws://127.0.0.1:{}", self.ws_port);
+		let deadline = Instant::now() + Duration::from_secs(60);
+		loop {
+			match WsClientBuilder::default().build(&url).await {
+				Ok(client) => return client,
+				Err(e) if Instant::now() > deadline =>
+					panic!("failed to connect to {}: {:?}", url, e),
+				Err(_) => sleep(Duration::from_millis(500)).await,
+			}
+		}
+	}
+
+	/// Scrape the Prometheus exporter of the node.
+	pub fn metrics(&self) -> String {
+		http_get(self.prometheus_port, "/metrics").1
+	}
+
+	/// Stop the node with `SIGINT` and wait for it to exit.
+	pub fn stop(mut self) -> ExitStatus {
+		kill(Pid::from_raw(self.child.id().try_into().unwrap()), SIGINT).unwrap();
+		wait_for(&mut self.child, 30).expect("node-template did not exit after SIGINT")
+	}
+}
+
+impl Drop for Node {
+	fn drop(&mut self) {
+		let _ = self.child.kill();
+		let _ = self.child.wait();
+	}
+}
+
+/// A network of authorities on `--chain local`, each with its own base path.
+pub struct Network {
+	/// The running nodes, `alice` first.
+	pub nodes: Vec<Node>,
+	base_paths: Vec<tempfile::TempDir>,
+}
+
+impl Network {
+	/// Start one authority per key, all connected to the first one.
+	pub fn start(keys: &[&str]) -> Network {
+		let base_paths = keys
+			.iter()
+			.map(|_| tempfile::tempdir().expect("failed to create base path"))
+			.collect::<Vec<_>>();
+		let nodes = start_authorities(keys, &base_paths);
+		Network { nodes, base_paths }
+	}
+
+	/// Stop all nodes gracefully and start them again on the same base paths.
+	pub fn restart(self, keys: &[&str]) -> Network {
+		let Network { nodes, base_paths } = self;
+		for node in nodes {
+			assert!(node.stop().success());
+		}
+		let nodes = start_authorities(keys, &base_paths);
+		Network { nodes, base_paths }
+	}
+}
+
+fn start_authorities(keys: &[&str], base_paths: &[tempfile::TempDir]) -> Vec<Node> {
+	let mut nodes = Vec::<Node>::new();
+	for (key, base_path) in keys.iter().zip(base_paths) {
+		let options = match nodes.first() {
+			None => NodeOptions { key: Some(key), is_bootnode: true, ..Default::default() },
+			Some(bootnode) => NodeOptions {
+				key: Some(key),
+				bootnode: Some(bootnode.bootnode_address()),
+				..Default::default()
+			},
+		};
+		nodes.push(Node::start(base_path.path(), options));
+	}
+	nodes
+}
+
+/// Call the RPC `method`, panicking on errors.
+pub async fn request<R: serde::de::DeserializeOwned>(
+	client: &WsClient,
+	method: &str,
+	params: Vec<Value>,
+) -> R {
+	let params =
+		if params.is_empty() { JsonRpcParams::NoParams } else { JsonRpcParams::Array(params) };
+	client
+		.request::<R>(method, params)
+		.await
+		.unwrap_or_else(|e| panic!("{} failed: {:?}", method, e))
+}
+
+/// Status code and body of a `GET` request to `path` on a local HTTP server.
+pub fn http_get(port: u16, path: &str) -> (u16, String) {
+	let mut stream =
+		TcpStream::connect(("127.0.0.1", port)).expect("failed to connect to the HTTP server");
+	write!(stream, "GET {} HTTP/1.0\r\nHost: 127.0.0.1\r\n\r\n", path).unwrap();
+	let mut response = String::new();
+	stream.read_to_string(&mut response).unwrap();
+
+	let status = response
+		.split(' ')
+		.nth(1)
+		.and_then(|status| status.parse().ok())
+		.expect("invalid HTTP response");
+	let body = response.splitn(2, "\r\n\r\n").nth(1).unwrap_or_default().to_string();
+	(status, body)
+}
+
+/// Value of the sample `name`, labels included, in Prometheus text format `metrics`.
+pub fn metric(metrics: &str, name: &str) -> Option<f64> {
+	metrics.lines().find_map(|line| {
+		let value = line.strip_prefix(name)?.strip_prefix(' ')?;
+		value.parse().ok()
+	})
+}
+
+/// Hash of the block at the given height of the best chain.
+pub async fn block_hash(client: &WsClient, number: u32) -> Hash {
+	request::<Option<Hash>>(client, "chain_getBlockHash", vec![json!(number)])
+		.await
+		.expect("block exists")
+}
+
+/// Header of the given block, or of the best block.
+pub async fn header(client: &WsClient, at: Option<Hash>) -> Header {
+	let params = at.map(|hash| vec![json!(hash)]).unwrap_or_default();
+	request::<Option<Header>>(client, "chain_getHeader", params)
+		.await
+		.expect("block exists")
+}
+
+/// Number of the best block.
+pub async fn best_number(client: &WsClient) -> u32 {
+	*header(client, None).await.number()
+}
+
+/// Number of the last finalized block.
+pub async fn finalized_number(client: &WsClient) -> u32 {
+	let hash = request::<Hash>(client, "chain_getFinalizedHead", vec![]).await;
+	*header(client, Some(hash)).await.number()
+}
+
+/// Wait until the best block is at least `number`.
+pub async fn wait_for_best(client: &WsClient, number: u32, timeout: Duration) {
+	let deadline = Instant::now() + timeout;
+	while best_number(client).await < number {
+		assert!(Instant::now() < deadline, "block #{} was not imported in time", number);
+		sleep(Duration::from_millis(500)).await;
+	}
+}
+
+/// Wait until the finalized block is at least `number`.
+pub async fn wait_for_finalized(client: &WsClient, number: u32, timeout: Duration) {
+	let deadline = Instant::now() + timeout;
+	while finalized_number(client).await < number {
+		assert!(Instant::now() < deadline, "block #{} was not finalized in time", number);
+		sleep(Duration::from_millis(500)).await;
+	}
+}
+
+/// Raw storage value at the given key.
+pub async fn storage(client: &WsClient, key: &[u8], at: Option<Hash>) -> Option<Vec<u8>> {
+	let mut params = vec![json!(Bytes(key.to_vec()))];
+	params.extend(at.map(|hash| json!(hash)));
+	request::<Option<Bytes>>(client, "state_getStorage", params)
+		.await
+		.map(|bytes| bytes.0)
+}
+
+/// Key of a storage value.
+pub fn storage_value_key(pallet: &str, item: &str) -> Vec<u8> {
+	[twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat()
+}
+
+/// Events deposited in the given block.
+pub async fn events(client: &WsClient, at: Hash) -> Vec<EventRecord> {
+	storage(client, &storage_value_key("System", "Events"), Some(at))
+		.await
+		.map(|raw| Decode::decode(&mut &raw[..]).expect("events are valid"))
+		.unwrap_or_default()
+}
+
+/// Wait for an event matching `filter` in the best chain.
+///
+/// Every block imported after the call is checked, returns the hash of the first block with a
+/// matching event.
+pub async fn wait_for_event(
+	client: &WsClient,
+	filter: impl Fn(&Event) -> bool,
+	timeout: Duration,
+) -> Hash {
+	let deadline = Instant::now() + timeout;
+	let mut next = best_number(client).await;
+	loop {
+		assert!(Instant::now() < deadline, "event was not deposited in time");
+		while next <= best_number(client).await {
+			let hash = block_hash(client, next).await;
+			if events(client, hash).await.iter().any(|record| filter(&record.event)) {
+				return hash
+			}
+			next += 1;
+		}
+		sleep(Duration::from_millis(500)).await;
+	}
+}
+
+/// Next nonce of the account, including transactions in the pool.
+pub async fn account_nonce(client: &WsClient, account: &node_template_runtime::AccountId) -> Index {
+	request::<Index>(client, "system_accountNextIndex", vec![json!(account.to_string())]).await
+}
+
+/// Weight, class and partial fee of `xt` at block `at`, as returned by `payment_queryInfo`.
+pub async fn query_info(client: &WsClient, xt: &UncheckedExtrinsic, at: Hash) -> Value {
+	request::<Value>(client, "payment_queryInfo", vec![json!(Bytes(xt.encode())), json!(at)]).await
+}
+
+/// Sign `call` with `signer`, submit it and return its hash.
+pub async fn submit(client: &WsClient, signer: &sr25519::Pair, call: Call) -> Hash {
+	let genesis_hash = block_hash(client, 0).await;
+	let nonce = account_nonce(client, &signer.public().into()).await;
+	let xt = signed_extrinsic(signer, nonce, genesis_hash, call);
+	request::<Hash>(client, "author_submitExtrinsic", vec![json!(Bytes(xt.encode()))]).await
+}
+
+/// Build an immortal extrinsic signed by `signer`, without tip.
+pub fn signed_extrinsic(
+	signer: &sr25519::Pair,
+	nonce: Index,
+	genesis_hash: Hash,
+	call: Call,
+) -> UncheckedExtrinsic {
+	let extra: SignedExtra = (
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+	);
+	let additional =
+		(VERSION.spec_version, VERSION.transaction_version, genesis_hash, genesis_hash, (), (), ());
+	let payload = sp_runtime::generic::SignedPayload::from_raw(call, extra, additional);
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+
+	UncheckedExtrinsic::new_signed(
+		call,
+		Address::Id(signer.public().into()),
+		signature.into(),
+		extra,
+	)
+}
@@ -0,0 +1,104 @@
+#![cfg(unix)]
+
+use codec::Encode;
+use node_template_runtime::{pallet_template, Call};
+use serde_json::{json, Value};
+use sp_core::Bytes;
+use sp_keyring::AccountKeyring;
+use std::time::Duration;
+use tempfile::tempdir;
+
+pub mod common;
+
+#[tokio::test]
+async fn dry_run_previews_extrinsics_and_calls() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions { key: Some("alice"), force_authoring: true, ..Default::default() },
+	);
+	let client = node.client().await;
+	common::wait_for_best(&client, 1, Duration::from_secs(60)).await;
+	let something = common::storage_value_key("TemplateModule", "Something");
+
+	// `Something` is not set yet, so `cause_error` fails.
+	let call = Call::TemplateModule(pallet_template::Call::cause_error());
+	let alice = AccountKeyring::Alice.to_account_id();
+	let result: Value = common::request(
+		&client,
+		"dev_dryRun",
+		vec![json!(Bytes(call.encode())), json!({ "signed": alice })],
+	)
+	.await;
+	assert_eq!(result["success"], json!(false));
+	assert_eq!(result["invalid"], Value::Null);
+	assert_eq!(result["error"]["pallet"], json!("TemplateModule"));
+	assert_eq!(result["error"]["name"], json!("NoneValue"));
+	assert_eq!(result["error"]["documentation"], json!("Error names should be descriptive."));
+
+	let genesis_hash = common::block_hash(&client, 0).await;
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	let xt = common::signed_extrinsic(&AccountKeyring::Alice.pair(), 0, genesis_hash, call);
+	let result: Value =
+		common::request(&client, "dev_dryRun", vec![json!(Bytes(xt.encode()))]).await;
+	assert_eq!(result["success"], json!(true));
+	assert!(result["weight"].as_u64().unwrap() > 0);
+	let events = result["events"].as_array().unwrap();
+	assert!(events.iter().any(|event| {
+		event["pallet"] == json!("TemplateModule") && event["name"] == json!("SomethingStored")
+	}));
+	let changes = result["storageChanges"].as_array().unwrap();
+	assert!(changes.iter().any(|change| change[0] == json!(Bytes(something.clone()))));
+
+	// Nothing was committed.
+	assert_eq!(common::storage(&client, &something, None).await, None);
+
+	// An extrinsic with a nonce from the future is reported as invalid.
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	let xt = common::signed_extrinsic(&AccountKeyring::Alice.pair(), 1, genesis_hash, call);
+	let result: Value =
+		common::request(&client, "dev_dryRun", vec![json!(Bytes(xt.encode()))]).await;
+	assert_eq!(result["success"], json!(false));
+	assert!(result["invalid"].as_str().unwrap().contains("Future"));
+
+	// A failing extrinsic is valid, its error is reported by `System::ExtrinsicFailed` as well.
+	let call = Call::TemplateModule(pallet_template::Call::cause_error());
+	let xt = common::signed_extrinsic(&AccountKeyring::Alice.pair(), 0, genesis_hash, call);
+	let result: Value =
+		common::request(&client, "dev_dryRun", vec![json!(Bytes(xt.encode()))]).await;
+	assert_eq!(result["invalid"], Value::Null);
+	assert_eq!(result["error"]["name"], json!("NoneValue"));
+	let failed = result["events"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.find(|event| event["name"] == json!("ExtrinsicFailed"))
+		.expect("ExtrinsicFailed is emitted");
+	assert_eq!(failed["error"]["pallet"], json!("TemplateModule"));
+	assert_eq!(failed["error"]["name"], json!("NoneValue"));
+}
+
+#[tokio::test]
+async fn module_errors_are_decoded_with_the_metadata() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::Node::start(base_path.path(), Default::default());
+	let client = node.client().await;
+	// Calls are encoded with the index of their pallet first.
+	let index = Call::TemplateModule(pallet_template::Call::cause_error()).encode()[0];
+
+	let error: Value =
+		common::request(&client, "dev_decodeModuleError", vec![json!(index), json!(1)]).await;
+	assert_eq!(
+		error,
+		json!({
+			"pallet": "TemplateModule",
+			"name": "StorageOverflow",
+			"documentation": "Errors should have helpful documentation associated with them.",
+		})
+	);
+
+	let error: Value =
+		common::request(&client, "dev_decodeModuleError", vec![json!(index), json!(42)]).await;
+	assert_eq!(error["pallet"], json!("TemplateModule"));
+	assert_eq!(error["name"], json!("Error #42"));
+}
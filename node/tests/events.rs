@@ -0,0 +1,60 @@
+#![cfg(unix)]
+
+use jsonrpsee_ws_client::types::{
+	traits::SubscriptionClient, v2::params::JsonRpcParams, Subscription,
+};
+use node_template_runtime::{pallet_template, Call};
+use serde_json::{json, Value};
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::AccountKeyring;
+use std::time::Duration;
+use tokio::time::timeout;
+
+pub mod common;
+
+#[tokio::test]
+async fn events_subscription_notifies_matching_events() {
+	// Blocks are only finalized with both authorities.
+	let network = common::Network::start(&["alice", "bob"]);
+	let client = network.nodes[0].client().await;
+	common::wait_for_finalized(&client, 1, Duration::from_secs(120)).await;
+
+	let alice = AccountKeyring::Alice.to_account_id().to_ss58check();
+	let subscribe = |account: &str| {
+		let filter =
+			json!({ "pallet": "TemplateModule", "name": "SomethingStored", "account": account });
+		client.subscribe::<Value>(
+			"events_subscribe",
+			JsonRpcParams::Array(vec![filter]),
+			"events_unsubscribe",
+		)
+	};
+	let mut alice_events = subscribe(&alice).await.expect("events_subscribe failed");
+	let bob = AccountKeyring::Bob.to_account_id().to_ss58check();
+	let mut bob_events = subscribe(&bob).await.expect("events_subscribe failed");
+
+	let call = Call::TemplateModule(pallet_template::Call::do_something(7));
+	common::submit(&client, &AccountKeyring::Alice.pair(), call).await;
+
+	// Notified once imported in the best chain, then once finalized.
+	let best = next(&mut alice_events).await;
+	assert_eq!(best["finalized"], json!(false));
+	assert_eq!(best["pallet"], json!("TemplateModule"));
+	assert_eq!(best["name"], json!("SomethingStored"));
+	assert_eq!(best["args"], json!([7, alice]));
+	assert_eq!(best["extrinsic"], json!(1));
+	let finalized = next(&mut alice_events).await;
+	assert_eq!(finalized["finalized"], json!(true));
+	assert_eq!(finalized["blockHash"], best["blockHash"]);
+
+	// Bob's subscription does not match the event.
+	assert!(timeout(Duration::from_secs(5), bob_events.next()).await.is_err());
+}
+
+async fn next(subscription: &mut Subscription<Value>) -> Value {
+	timeout(Duration::from_secs(120), subscription.next())
+		.await
+		.expect("event was not notified in time")
+		.expect("subscription failed")
+		.expect("subscription closed")
+}
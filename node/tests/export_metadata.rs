@@ -0,0 +1,54 @@
+#![cfg(unix)]
+
+use codec::{Decode, Encode};
+use frame_metadata::RuntimeMetadataPrefixed;
+use node_template_runtime::{Runtime, WASM_BINARY};
+use serde_json::{json, Value};
+use std::fs;
+use tempfile::tempdir;
+
+pub mod common;
+
+#[test]
+fn export_metadata_writes_scale_json_and_types() {
+	let dir = tempdir().expect("could not create a temp dir");
+	let built_in = dir.path().join("built-in");
+	let built_in_arg = built_in.to_str().unwrap();
+	assert!(common::run_cmd(&["export-metadata", "--output-dir", built_in_arg]).success());
+
+	// The metadata of the Wasm runtime is the one of the native runtime.
+	let scale = fs::read(built_in.join("metadata.scale")).expect("metadata.scale was written");
+	assert_eq!(scale, Runtime::metadata().encode());
+	assert!(RuntimeMetadataPrefixed::decode(&mut &scale[..]).is_ok());
+
+	let json: Value =
+		serde_json::from_slice(&fs::read(built_in.join("metadata.json")).unwrap()).unwrap();
+	assert_eq!(json["version"]["specName"], json!("node-template"));
+	let modules = &json["metadata"][1]["V13"]["modules"];
+	assert!(modules
+		.as_array()
+		.unwrap()
+		.iter()
+		.any(|module| module["name"] == json!("TemplateModule")));
+
+	let types = fs::read_to_string(built_in.join("types.ts")).unwrap();
+	assert!(types.contains("export default {"), "{}", types);
+	assert!(types.contains("Address: 'MultiAddress',"), "{}", types);
+	assert!(types.contains("Balance: 'u128',"), "{}", types);
+
+	// The same files from a Wasm file.
+	let wasm = dir.path().join("runtime.wasm");
+	fs::write(&wasm, WASM_BINARY.expect("the Wasm runtime is built")).unwrap();
+	let from_file = dir.path().join("from-file");
+	let args = [
+		"export-metadata",
+		"--wasm",
+		wasm.to_str().unwrap(),
+		"--output-dir",
+		from_file.to_str().unwrap(),
+	];
+	assert!(common::run_cmd(&args).success());
+	for file in &["metadata.scale", "metadata.json", "types.ts"] {
+		assert_eq!(fs::read(from_file.join(file)).unwrap(), fs::read(built_in.join(file)).unwrap());
+	}
+}
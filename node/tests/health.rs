@@ -0,0 +1,58 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::time::{sleep, Instant};
+
+pub mod common;
+
+/// Wait for `path` of the health server on `port` to answer `200 OK`.
+async fn wait_for_ok(port: u16, path: &str, timeout: Duration) -> String {
+	let deadline = Instant::now() + timeout;
+	loop {
+		let (status, body) = common::http_get(port, path);
+		if status == 200 {
+			return body
+		}
+		assert!(Instant::now() < deadline, "{} answered {}: {}", path, status, body);
+		sleep(Duration::from_secs(1)).await;
+	}
+}
+
+#[tokio::test]
+async fn probes_report_readiness_and_authoring() {
+	let (alice_dir, bob_dir) = (tempdir().unwrap(), tempdir().unwrap());
+	let alice_port = common::free_port();
+	let alice_port_arg = alice_port.to_string();
+	let alice = common::Node::start(
+		alice_dir.path(),
+		common::NodeOptions {
+			key: Some("alice"),
+			is_bootnode: true,
+			extra_args: vec!["--health-port", &alice_port_arg],
+			..Default::default()
+		},
+	);
+	alice.client().await;
+
+	assert_eq!(common::http_get(alice_port, "/health"), (200, "OK".into()));
+	assert!(common::http_get(alice_port, "/authoring").1.starts_with("Authoring as 5Grw"));
+	// Alone on the `local` chain, Alice has no peers.
+	assert_eq!(common::http_get(alice_port, "/ready"), (503, "No peers".into()));
+	assert_eq!(common::http_get(alice_port, "/unknown").0, 404);
+
+	// Bob is not an authority here, he only follows the chain.
+	let bob_port = common::free_port();
+	let bob_port_arg = bob_port.to_string();
+	let _bob = common::Node::start(
+		bob_dir.path(),
+		common::NodeOptions {
+			bootnode: Some(alice.bootnode_address()),
+			extra_args: vec!["--health-port", &bob_port_arg, "--ready-max-finality-lag", "1000"],
+			..Default::default()
+		},
+	);
+
+	wait_for_ok(bob_port, "/ready", Duration::from_secs(120)).await;
+	assert_eq!(common::http_get(bob_port, "/authoring").0, 503);
+}
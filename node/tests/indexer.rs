@@ -0,0 +1,74 @@
+#![cfg(unix)]
+
+use node_template_runtime::{pallet_template, Call, Event};
+use serde_json::{json, Value};
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::AccountKeyring;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::time::{sleep, Instant};
+
+pub mod common;
+
+#[tokio::test]
+async fn indexer_stores_decoded_extrinsics_and_events() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let index = base_path.path().join("index.sqlite");
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions {
+			key: Some("alice"),
+			force_authoring: true,
+			extra_args: vec!["--indexer", index.to_str().unwrap()],
+			..Default::default()
+		},
+	);
+	let client = node.client().await;
+	common::wait_for_best(&client, 1, Duration::from_secs(60)).await;
+
+	let alice = AccountKeyring::Alice.to_account_id().to_ss58check();
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	common::submit(&client, &AccountKeyring::Alice.pair(), call).await;
+	common::wait_for_event(
+		&client,
+		|event| matches!(event, Event::TemplateModule(pallet_template::Event::SomethingStored(..))),
+		Duration::from_secs(60),
+	)
+	.await;
+
+	// The indexer follows the imports asynchronously.
+	let filter = json!({ "pallet": "TemplateModule", "name": "SomethingStored" });
+	let deadline = Instant::now() + Duration::from_secs(30);
+	let events = loop {
+		let events: Vec<Value> =
+			common::request(&client, "indexer_events", vec![filter.clone()]).await;
+		if !events.is_empty() {
+			break events
+		}
+		assert!(Instant::now() < deadline, "event was not indexed in time");
+		sleep(Duration::from_millis(500)).await;
+	};
+	assert_eq!(events.len(), 1);
+	assert_eq!(events[0]["args"], json!([42, alice]));
+	let block_number = events[0]["blockNumber"].clone();
+
+	let extrinsics: Vec<Value> = common::request(
+		&client,
+		"indexer_extrinsics",
+		vec![json!({ "account": alice, "from": block_number, "to": block_number })],
+	)
+	.await;
+	assert_eq!(extrinsics.len(), 1);
+	assert_eq!(extrinsics[0]["signer"], json!(alice));
+	assert_eq!(extrinsics[0]["nonce"], json!(0));
+	assert_eq!(extrinsics[0]["success"], json!(true));
+	assert_eq!(extrinsics[0]["call"]["pallet"], json!("TemplateModule"));
+	assert_eq!(extrinsics[0]["call"]["name"], json!("do_something"));
+	assert_eq!(extrinsics[0]["call"]["args"], json!({ "something": 42 }));
+
+	// Bob did nothing.
+	let bob = AccountKeyring::Bob.to_account_id().to_ss58check();
+	let extrinsics: Vec<Value> =
+		common::request(&client, "indexer_extrinsics", vec![json!({ "account": bob })]).await;
+	assert!(extrinsics.is_empty());
+}
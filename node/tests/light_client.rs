@@ -0,0 +1,48 @@
+#![cfg(unix)]
+
+use node_template_runtime::{pallet_template, Call, Event};
+use sp_keyring::AccountKeyring;
+use sp_runtime::traits::Header as _;
+use std::time::Duration;
+use tempfile::tempdir;
+
+pub mod common;
+
+#[tokio::test]
+async fn light_client_serves_nonces_and_fees_like_a_full_node() {
+	let network = common::Network::start(&["alice", "bob"]);
+	let full = network.nodes[0].client().await;
+	common::wait_for_best(&full, 1, Duration::from_secs(120)).await;
+
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	common::submit(&full, &AccountKeyring::Alice.pair(), call.clone()).await;
+	let included = common::wait_for_event(
+		&full,
+		|event| matches!(event, Event::TemplateModule(pallet_template::Event::SomethingStored(..))),
+		Duration::from_secs(60),
+	)
+	.await;
+	let number = *common::header(&full, Some(included)).await.number();
+
+	let base_path = tempdir().expect("could not create a temp dir");
+	let light_node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions {
+			bootnode: Some(network.nodes[0].bootnode_address()),
+			extra_args: vec!["--light"],
+			..Default::default()
+		},
+	);
+	let light = light_node.client().await;
+	common::wait_for_best(&light, number, Duration::from_secs(120)).await;
+
+	let alice = AccountKeyring::Alice.to_account_id();
+	assert_eq!(common::account_nonce(&light, &alice).await, 1);
+	assert_eq!(common::account_nonce(&full, &alice).await, 1);
+
+	let genesis_hash = common::block_hash(&full, 0).await;
+	let xt = common::signed_extrinsic(&AccountKeyring::Alice.pair(), 1, genesis_hash, call);
+	let info = common::query_info(&full, &xt, included).await;
+	assert!(!info["partialFee"].is_null());
+	assert_eq!(common::query_info(&light, &xt, included).await, info);
+}
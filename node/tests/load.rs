@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+use serde_json::{json, Value};
+use std::{path::Path, process::Command, time::Duration};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Run `load` against `nodes`, returns its output and report.
+fn load(nodes: &[&common::Node], args: &[&str], report: &Path) -> (String, Value) {
+	let mut cmd = Command::new(env!("CARGO_BIN_EXE_node-template"));
+	cmd.arg("load").args(args).arg("--output").arg(report);
+	for node in nodes {
+		cmd.args(&["--url", &format!("ws://127.0.0.1:{}", node.ws_port)]);
+	}
+	let output = cmd.output().expect("failed to run node-template");
+	let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+	assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+	let report = std::fs::read_to_string(report).expect("report was written");
+	(stdout, serde_json::from_str(&report).expect("report is JSON"))
+}
+
+#[tokio::test]
+async fn load_submits_to_all_nodes_and_reports_inclusion() {
+	let network = common::Network::start(&["alice", "bob"]);
+	let nodes = network.nodes.iter().collect::<Vec<_>>();
+	common::wait_for_best(&nodes[0].client().await, 1, Duration::from_secs(120)).await;
+	let reports = tempdir().expect("could not create a temp dir");
+
+	let args = ["--accounts", "4", "--tps", "4", "--duration", "5", "--extrinsic", "transfer"];
+	let (stdout, report) = load(&nodes, &args, &reports.path().join("transfer.json"));
+	assert!(stdout.contains("Funding 4 accounts"), "{}", stdout);
+	assert_eq!(report["extrinsic"], json!("Balances::transfer"));
+	assert_eq!(report["submitted"], json!(20));
+	assert_eq!(report["included"], json!(20), "{}", report);
+	assert_eq!(report["rejected"], json!({}));
+	assert_eq!(report["dropped"], json!({}));
+	assert_eq!(report["pending"], json!(0));
+	assert!(report["achievedTps"].as_f64().unwrap() > 0.0);
+	let latency = &report["latencyMs"];
+	assert!(latency["p50"].as_f64().unwrap() <= latency["max"].as_f64().unwrap());
+	for node in report["nodes"].as_array().unwrap() {
+		assert_eq!(node["submitted"], json!(10));
+		assert_eq!(node["included"], json!(10));
+	}
+
+	// The accounts are still funded, and continue from the nonces they reached.
+	let args = ["--accounts", "4", "--tps", "4", "--duration", "2", "--extrinsic", "do-something"];
+	let (stdout, report) = load(&nodes, &args, &reports.path().join("do-something.json"));
+	assert!(!stdout.contains("Funding"), "{}", stdout);
+	assert_eq!(report["extrinsic"], json!("TemplateModule::do_something"));
+	assert_eq!(report["included"], json!(8), "{}", report);
+	assert_eq!(report["rejected"], json!({}));
+}
+
+#[test]
+fn load_rejects_rates_without_a_submission_period() {
+	for tps in &["0", "-1", "inf", "NaN", "1e10"] {
+		let output = Command::new(env!("CARGO_BIN_EXE_node-template"))
+			.args(&["load", "--url", "ws://127.0.0.1:1", &format!("--tps={}", tps)])
+			.output()
+			.expect("failed to run node-template");
+		assert!(!output.status.success(), "--tps {}", tps);
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		assert!(stderr.contains("`--tps` must be"), "--tps {}: {}", tps, stderr);
+	}
+}
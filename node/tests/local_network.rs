@@ -0,0 +1,109 @@
+#![cfg(unix)]
+
+use node_template_runtime::{pallet_template, Call, Event};
+use sp_keyring::AccountKeyring;
+use sp_runtime::traits::Header as _;
+use std::time::Duration;
+
+pub mod common;
+
+#[tokio::test]
+async fn authorities_produce_and_finalize_blocks() {
+	let network = common::Network::start(&["alice", "bob"]);
+
+	for node in &network.nodes {
+		let client = node.client().await;
+		common::wait_for_finalized(&client, 3, Duration::from_secs(120)).await;
+	}
+}
+
+#[tokio::test]
+async fn template_module_call_is_applied_on_all_nodes() {
+	let network = common::Network::start(&["alice", "bob"]);
+	let alice = network.nodes[0].client().await;
+	let bob = network.nodes[1].client().await;
+	common::wait_for_finalized(&alice, 1, Duration::from_secs(120)).await;
+
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	common::submit(&alice, &AccountKeyring::Alice.pair(), call).await;
+
+	let expected = AccountKeyring::Alice.to_account_id();
+	let included = common::wait_for_event(
+		&alice,
+		|event| match event {
+			Event::TemplateModule(pallet_template::Event::SomethingStored(42, who)) =>
+				who == &expected,
+			_ => false,
+		},
+		Duration::from_secs(60),
+	)
+	.await;
+
+	// Once finalized, the other authority must agree on the resulting state.
+	let number = *common::header(&alice, Some(included)).await.number();
+	common::wait_for_finalized(&bob, number, Duration::from_secs(120)).await;
+	let key = common::storage_value_key("TemplateModule", "Something");
+	assert_eq!(
+		common::storage(&bob, &key, Some(included)).await,
+		Some(42u32.to_le_bytes().to_vec())
+	);
+}
+
+#[tokio::test]
+async fn grandpa_observer_follows_finality() {
+	let network = common::Network::start(&["alice", "bob"]);
+	let base_path = tempfile::tempdir().expect("could not create a temp dir");
+	let observer = common::Node::start(
+		base_path.path(),
+		common::NodeOptions {
+			bootnode: Some(network.nodes[0].bootnode_address()),
+			extra_args: vec!["--grandpa-observer"],
+			..Default::default()
+		},
+	);
+
+	common::wait_for_finalized(&observer.client().await, 3, Duration::from_secs(120)).await;
+}
+
+#[test]
+fn authorities_cannot_run_the_grandpa_observer() {
+	let base_path = tempfile::tempdir().expect("could not create a temp dir");
+	let base_path_arg = base_path.path().to_str().unwrap();
+	let status = common::run_cmd(&[
+		"--chain",
+		"local",
+		"--alice",
+		"--grandpa-observer",
+		"--base-path",
+		base_path_arg,
+	]);
+	assert!(!status.success());
+}
+
+#[tokio::test]
+async fn authorities_author_with_backoff_enabled() {
+	let base_path = tempfile::tempdir().expect("could not create a temp dir");
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions {
+			key: Some("alice"),
+			force_authoring: true,
+			extra_args: vec!["--backoff-authoring"],
+			..Default::default()
+		},
+	);
+
+	// Far below the default slack of unfinalized blocks, authoring is not slowed down.
+	common::wait_for_best(&node.client().await, 3, Duration::from_secs(60)).await;
+}
+
+#[test]
+fn backoff_options_require_backoff_authoring() {
+	let output = std::process::Command::new(env!("CARGO_BIN_EXE_node-template"))
+		.args(&["--dev", "--tmp", "--backoff-max-interval", "10"])
+		.output()
+		.expect("failed to run node-template");
+	assert!(!output.status.success());
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(stderr.contains("--backoff-authoring"), "{}", stderr);
+}
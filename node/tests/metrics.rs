@@ -0,0 +1,58 @@
+#![cfg(unix)]
+
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::AccountKeyring;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::time::{sleep, Instant};
+
+pub mod common;
+
+#[tokio::test]
+async fn authoring_and_finality_metrics_are_exported() {
+	let network = common::Network::start(&["alice", "bob"]);
+	let alice = &network.nodes[0];
+	common::wait_for_finalized(&alice.client().await, 3, Duration::from_secs(120)).await;
+
+	let metrics = alice.metrics();
+	let authority = AccountKeyring::Alice.public().to_ss58check();
+	let claimed = format!("substrate_aura_slots_claimed_total{{authority=\"{}\"}}", authority);
+	assert!(common::metric(&metrics, &claimed).unwrap() > 0.0);
+	assert!(common::metric(&metrics, "substrate_proposal_duration_seconds_count").unwrap() > 0.0);
+	assert!(common::metric(&metrics, "substrate_proposal_extrinsics_count").unwrap() > 0.0);
+	let finality_delay = "substrate_finality_delay_seconds_count{mode=\"voter\"}";
+	assert!(common::metric(&metrics, finality_delay).unwrap() > 0.0);
+	assert!(common::metric(&metrics, "substrate_finality_lag_blocks{mode=\"voter\"}").is_some());
+}
+
+#[tokio::test]
+async fn authoring_backs_off_when_finality_stalls() {
+	// `Bob` is not running, so `Alice` alone cannot finalize anything.
+	let base_path = tempdir().expect("could not create a temp dir");
+	let alice = common::Node::start(
+		base_path.path(),
+		common::NodeOptions {
+			key: Some("alice"),
+			force_authoring: true,
+			extra_args: vec![
+				"--backoff-authoring",
+				"--backoff-unfinalized-slack",
+				"1",
+				"--backoff-max-interval",
+				"4",
+			],
+			..Default::default()
+		},
+	);
+	common::wait_for_best(&alice.client().await, 2, Duration::from_secs(120)).await;
+
+	let deadline = Instant::now() + Duration::from_secs(180);
+	loop {
+		let skipped = common::metric(&alice.metrics(), "substrate_authoring_skipped_slots_total");
+		if skipped.map_or(false, |skipped| skipped > 0.0) {
+			break
+		}
+		assert!(Instant::now() < deadline, "no slot was skipped");
+		sleep(Duration::from_secs(1)).await;
+	}
+}
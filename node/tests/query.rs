@@ -0,0 +1,71 @@
+#![cfg(unix)]
+
+use node_template_runtime::{pallet_template, Call, Event};
+use serde_json::{json, Value};
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::AccountKeyring;
+use std::{process::Command, time::Duration};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn query(args: &[&str]) -> Vec<Value> {
+	let output = Command::new(env!("CARGO_BIN_EXE_node-template"))
+		.arg("query")
+		.args(args)
+		.output()
+		.expect("failed to run node-template");
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+	String::from_utf8(output.stdout)
+		.expect("output is UTF-8")
+		.lines()
+		.map(|line| serde_json::from_str(line).expect("output is JSON"))
+		.collect()
+}
+
+#[tokio::test]
+async fn query_reads_storage_by_name_online_and_offline() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions { key: Some("alice"), force_authoring: true, ..Default::default() },
+	);
+	let client = node.client().await;
+	common::wait_for_best(&client, 1, Duration::from_secs(60)).await;
+	let url = format!("ws://127.0.0.1:{}", node.ws_port);
+
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	common::submit(&client, &AccountKeyring::Alice.pair(), call).await;
+	let stored = common::wait_for_event(
+		&client,
+		|event| matches!(event, Event::TemplateModule(pallet_template::Event::SomethingStored(..))),
+		Duration::from_secs(60),
+	)
+	.await;
+
+	let at = format!("{:?}", stored);
+	assert_eq!(query(&["TemplateModule", "Something", "--url", &url, "--at", &at]), [json!(42)]);
+	let genesis = format!("{:?}", common::block_hash(&client, 0).await);
+	assert_eq!(
+		query(&["TemplateModule", "Something", "--url", &url, "--at", &genesis]),
+		[Value::Null]
+	);
+
+	let alice = AccountKeyring::Alice.to_account_id().to_ss58check();
+	let account = query(&["System", "Account", &alice, "--url", &url, "--at", &at]);
+	assert_eq!(account[0]["nonce"], json!(1));
+	assert!(account[0]["data"]["free"].as_str().unwrap().parse::<u128>().unwrap() > 0);
+
+	// Every account of the genesis is endowed.
+	let accounts = query(&["System", "Account", "--url", &url, "--at", &genesis]);
+	assert!(accounts.iter().any(|entry| entry["keys"] == json!([alice])));
+	assert!(accounts.iter().all(|entry| entry["value"]["nonce"] == json!(0)));
+
+	assert!(node.stop().success());
+	let offline = |args: &[&str]| {
+		let base_path = base_path.path().to_str().unwrap();
+		query(&[&["--offline", "--chain", "local", "--base-path", base_path], args].concat())
+	};
+	assert_eq!(offline(&["TemplateModule", "Something"]), [json!(42)]);
+	assert_eq!(offline(&["TemplateModule", "Something", "--at", &genesis]), [Value::Null]);
+}
@@ -0,0 +1,86 @@
+#![cfg(unix)]
+
+use node_template_runtime::{Address, BalancesCall, Call, Event};
+use serde_json::{json, Value};
+use sp_keyring::AccountKeyring;
+use std::{path::Path, process::Command, time::Duration};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn replay_block(base_path: &Path, block: &str, execution: &str) -> Value {
+	let output = Command::new(env!("CARGO_BIN_EXE_node-template"))
+		.args(&["replay-block", "--chain", "local", "--base-path", base_path.to_str().unwrap()])
+		.args(&[block, "--execution", execution])
+		.output()
+		.expect("failed to run node-template");
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+	serde_json::from_slice(&output.stdout).expect("report is JSON")
+}
+
+/// Keys written by `phase`, with the method writing them.
+fn writes(phase: &Value) -> Vec<(Value, Value)> {
+	phase["writes"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.map(|access| (access["method"].clone(), access["key"].clone()))
+		.collect()
+}
+
+#[tokio::test]
+async fn replay_block_traces_storage_accesses_per_extrinsic() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions { key: Some("alice"), force_authoring: true, ..Default::default() },
+	);
+	let client = node.client().await;
+	common::wait_for_best(&client, 1, Duration::from_secs(60)).await;
+	let dest = Address::Id(AccountKeyring::Bob.to_account_id());
+	let call = Call::Balances(BalancesCall::transfer(dest, 1_000_000_000_000));
+	common::submit(&client, &AccountKeyring::Alice.pair(), call).await;
+	let transferred = common::wait_for_event(
+		&client,
+		|event| matches!(event, Event::Balances(pallet_balances::Event::Transfer(..))),
+		Duration::from_secs(60),
+	)
+	.await;
+	drop(client);
+	assert!(node.stop().success());
+
+	let block = format!("{:?}", transferred);
+	let wasm = replay_block(base_path.path(), &block, "Wasm");
+	assert_eq!(wasm["hash"], json!(block));
+	assert_eq!(wasm["stateRootMatches"], json!(true));
+
+	let phases = wasm["phases"].as_array().unwrap();
+	assert_eq!(phases.first().unwrap()["phase"], json!("initialize"));
+	assert_eq!(phases.last().unwrap()["phase"], json!("finalize"));
+	let transfer = phases
+		.iter()
+		.find(|phase| phase["call"]["name"] == json!("transfer"))
+		.expect("the transfer is replayed");
+	assert_eq!(transfer["call"]["pallet"], json!("Balances"));
+	assert_eq!(transfer["success"], json!(true));
+	assert!(transfer["weight"].as_u64().unwrap() > 0);
+	assert!(transfer["timeNs"].as_u64().unwrap() > 0);
+
+	let account =
+		|access: &&Value| access["pallet"] == json!("System") && access["item"] == json!("Account");
+	let read = transfer["reads"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.find(account)
+		.expect("accounts are read");
+	assert!(read["size"].as_u64().unwrap() > 0);
+	assert!(read["valueHash"].is_string());
+	// The accounts of both Alice and Bob are written.
+	assert!(transfer["writes"].as_array().unwrap().iter().filter(account).count() >= 2);
+
+	let native = replay_block(base_path.path(), &block, "Native");
+	assert_eq!(native["stateRootMatches"], json!(true));
+	let native_transfer = &native["phases"][transfer["index"].as_u64().unwrap() as usize + 1];
+	assert_eq!(writes(native_transfer), writes(transfer));
+}
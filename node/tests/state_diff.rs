@@ -0,0 +1,73 @@
+#![cfg(unix)]
+
+use node_template_runtime::{pallet_template, Call, Event};
+use serde_json::{json, Value};
+use sp_core::crypto::Ss58Codec;
+use sp_keyring::AccountKeyring;
+use std::{path::Path, process::Command, time::Duration};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn state_diff(base_path: &Path, args: &[&str]) -> Vec<Value> {
+	let output = Command::new(env!("CARGO_BIN_EXE_node-template"))
+		.args(&["state-diff", "--chain", "local", "--base-path", base_path.to_str().unwrap()])
+		.args(args)
+		.output()
+		.expect("failed to run node-template");
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+	String::from_utf8(output.stdout)
+		.expect("output is UTF-8")
+		.lines()
+		.map(|line| serde_json::from_str(line).expect("output is JSON"))
+		.collect()
+}
+
+#[tokio::test]
+async fn state_diff_lists_decoded_changes_between_blocks() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions { key: Some("alice"), force_authoring: true, ..Default::default() },
+	);
+	let client = node.client().await;
+	common::wait_for_best(&client, 1, Duration::from_secs(60)).await;
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	common::submit(&client, &AccountKeyring::Alice.pair(), call).await;
+	let stored = common::wait_for_event(
+		&client,
+		|event| matches!(event, Event::TemplateModule(pallet_template::Event::SomethingStored(..))),
+		Duration::from_secs(60),
+	)
+	.await;
+	let genesis = format!("{:?}", common::block_hash(&client, 0).await);
+	let stored = format!("{:?}", stored);
+	drop(client);
+	assert!(node.stop().success());
+
+	let template = ["--from", &genesis, "--to", &stored, "--pallet", "TemplateModule"];
+	let changes = state_diff(base_path.path(), &template);
+	assert_eq!(changes.len(), 1, "{:?}", changes);
+	assert_eq!(changes[0]["change"], json!("added"));
+	assert_eq!(changes[0]["pallet"], json!("TemplateModule"));
+	assert_eq!(changes[0]["item"], json!("Something"));
+	assert_eq!(changes[0]["to"], json!(42));
+
+	// Reversed, the item is removed.
+	let changes = state_diff(base_path.path(), &["--from", &stored, "--to", &genesis]);
+	assert!(changes.iter().any(|change| change["item"] == json!("Something") &&
+		change["change"] == json!("removed") &&
+		change["from"] == json!(42)));
+
+	let changes = state_diff(base_path.path(), &["--from", &genesis, "--to", &stored]);
+	let alice = AccountKeyring::Alice.to_account_id().to_ss58check();
+	let account = changes
+		.iter()
+		.find(|change| change["item"] == json!("Account") && change["keys"] == json!([alice]))
+		.expect("the account of the signer changed");
+	assert_eq!(account["change"], json!("modified"));
+	assert_eq!(account["pallet"], json!("System"));
+	assert_eq!(account["from"]["nonce"], json!(0));
+	assert_eq!(account["to"]["nonce"], json!(1));
+	assert!(changes.iter().all(|change| change["key"] != json!("0x3a636f6465")));
+}
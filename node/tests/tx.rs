@@ -0,0 +1,43 @@
+#![cfg(unix)]
+
+use codec::Encode;
+use std::{process::Command, time::Duration};
+
+pub mod common;
+
+fn tx(ws_port: u16, args: &[&str]) -> (bool, String) {
+	let output = Command::new(env!("CARGO_BIN_EXE_node-template"))
+		.arg("tx")
+		.args(args)
+		.args(&["--suri", "//Alice", "--url", &format!("ws://127.0.0.1:{}", ws_port)])
+		.output()
+		.expect("failed to run node-template");
+	let text = [output.stdout, output.stderr].concat();
+	(output.status.success(), String::from_utf8_lossy(&text).into_owned())
+}
+
+#[tokio::test]
+async fn tx_signs_submits_and_watches_until_finalized() {
+	// Blocks are only finalized with both authorities.
+	let network = common::Network::start(&["alice", "bob"]);
+	let node = &network.nodes[0];
+	let client = node.client().await;
+	common::wait_for_finalized(&client, 1, Duration::from_secs(120)).await;
+
+	// `Something` is not set yet.
+	let (success, output) = tx(node.ws_port, &["TemplateModule", "cause_error"]);
+	assert!(!success, "{}", output);
+	assert!(output.contains("TemplateModule::NoneValue"), "{}", output);
+	assert!(output.contains("Finalized in block"), "{}", output);
+
+	let (success, output) = tx(node.ws_port, &["TemplateModule", "do_something", "42"]);
+	assert!(success, "{}", output);
+	assert!(output.contains("TemplateModule::SomethingStored"), "{}", output);
+	assert!(output.contains("Finalized in block"), "{}", output);
+	let something = common::storage_value_key("TemplateModule", "Something");
+	assert_eq!(common::storage(&client, &something, None).await, Some(42u32.encode()));
+
+	let (success, output) = tx(node.ws_port, &["TemplateModule", "do_something", "nope"]);
+	assert!(!success);
+	assert!(output.contains("TemplateModule::do_something: something"), "{}", output);
+}
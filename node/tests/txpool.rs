@@ -0,0 +1,88 @@
+#![cfg(unix)]
+
+use codec::Encode;
+use node_template_runtime::{pallet_template, Call, Hash};
+use serde_json::{json, Value};
+use sp_core::{crypto::Ss58Codec, Bytes};
+use sp_keyring::AccountKeyring;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::time::{sleep, Instant};
+
+pub mod common;
+
+#[tokio::test]
+async fn txpool_lists_and_removes_stuck_transactions() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::Node::start(
+		base_path.path(),
+		common::NodeOptions { key: Some("alice"), force_authoring: true, ..Default::default() },
+	);
+	let client = node.client().await;
+	common::wait_for_best(&client, 1, Duration::from_secs(60)).await;
+
+	// Nonce 0 is never sent, so this one is stuck in the future queue.
+	let genesis_hash = common::block_hash(&client, 0).await;
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	let xt = common::signed_extrinsic(&AccountKeyring::Alice.pair(), 1, genesis_hash, call);
+	let hash: Hash =
+		common::request(&client, "author_submitExtrinsic", vec![json!(Bytes(xt.encode()))]).await;
+
+	let content: Value = common::request(&client, "txpool_content", vec![]).await;
+	let alice = AccountKeyring::Alice.to_account_id().to_ss58check();
+	assert_eq!(content["ready"], json!({}));
+	let stuck = &content["future"][&alice];
+	assert_eq!(stuck.as_array().unwrap().len(), 1);
+	assert_eq!(stuck[0]["hash"], json!(hash));
+	assert_eq!(stuck[0]["nonce"], json!(1));
+	assert_eq!(stuck[0]["requires"].as_array().unwrap().len(), 1);
+
+	let status: Value = common::request(&client, "txpool_status", vec![]).await;
+	assert_eq!(status["future"]["count"], json!(1));
+	assert!(status["future"]["maxCount"].as_u64().unwrap() > 0);
+
+	assert!(common::request::<bool>(&client, "txpool_remove", vec![json!(hash)]).await);
+	assert!(!common::request::<bool>(&client, "txpool_remove", vec![json!(hash)]).await);
+	let content: Value = common::request(&client, "txpool_content", vec![]).await;
+	assert_eq!(content["future"], json!({}));
+}
+
+#[tokio::test]
+async fn txpool_is_restored_after_restart() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let options = common::NodeOptions {
+		key: Some("alice"),
+		force_authoring: true,
+		extra_args: vec!["--persist-txpool"],
+		..Default::default()
+	};
+	let node = common::Node::start(base_path.path(), options.clone());
+	let client = node.client().await;
+	common::wait_for_best(&client, 1, Duration::from_secs(60)).await;
+
+	let genesis_hash = common::block_hash(&client, 0).await;
+	let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+	let xt = common::signed_extrinsic(&AccountKeyring::Alice.pair(), 1, genesis_hash, call);
+	let hash: Hash =
+		common::request(&client, "author_submitExtrinsic", vec![json!(Bytes(xt.encode()))]).await;
+	drop(client);
+	assert!(node.stop().success());
+
+	let saved = base_path.path().join("chains/local_testnet/txpool");
+	assert!(saved.exists());
+
+	let node = common::Node::start(base_path.path(), options);
+	let client = node.client().await;
+	let alice = AccountKeyring::Alice.to_account_id().to_ss58check();
+	let deadline = Instant::now() + Duration::from_secs(30);
+	loop {
+		let content: Value = common::request(&client, "txpool_content", vec![]).await;
+		if content["future"][&alice][0]["hash"] == json!(hash) {
+			break
+		}
+		assert!(Instant::now() < deadline, "transaction was not restored");
+		sleep(Duration::from_millis(500)).await;
+	}
+	// The file is consumed on start.
+	assert!(!saved.exists());
+}
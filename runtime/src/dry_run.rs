@@ -0,0 +1,61 @@
+//! Preview of the effects of an extrinsic or a call, without committing them.
+//!
+//! The runtime API is meant to be called right after `Core_initialize_block`, in the same runtime
+//! API instance, so that the extrinsic is applied on top of the block being previewed. Its
+//! changes are discarded with the instance.
+
+use crate::{AccountId, Call, Event, Executive, Hash, System, UncheckedExtrinsic};
+use codec::{Decode, Encode};
+use frame_support::weights::{extract_actual_weight, GetDispatchInfo, Weight};
+use sp_runtime::{traits::Dispatchable, ApplyExtrinsicResult, RuntimeDebug};
+use sp_std::prelude::*;
+
+/// Origin of a dry-run call.
+pub type DryRunOrigin = frame_system::RawOrigin<AccountId>;
+
+/// An event emitted during a dry-run.
+pub type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// The effects of a dry-run.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct DryRunEffects {
+	/// Outcome of the dispatch, or why the extrinsic is invalid and was not dispatched.
+	pub result: ApplyExtrinsicResult,
+	/// Events emitted by the extrinsic or the call.
+	pub events: Vec<EventRecord>,
+	/// Weight actually consumed, including the base weight of an extrinsic.
+	pub weight: Weight,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Apply extrinsics or dispatch calls on top of an initialized block.
+	pub trait DryRunApi {
+		/// Apply an extrinsic, checking its signature, nonce and fee payment like a block would.
+		fn dry_run_extrinsic(extrinsic: Block::Extrinsic) -> DryRunEffects;
+
+		/// Dispatch a call with the given origin, without any transaction checks or fees.
+		fn dry_run_call(origin: DryRunOrigin, call: Call) -> DryRunEffects;
+	}
+}
+
+/// Apply `extrinsic` to the current block.
+pub fn extrinsic(extrinsic: UncheckedExtrinsic) -> DryRunEffects {
+	let weight_before = System::block_weight().total();
+	let result = Executive::apply_extrinsic(extrinsic);
+	let weight = System::block_weight().total().saturating_sub(weight_before);
+
+	effects(result, weight)
+}
+
+/// Dispatch `call` with `origin` in the current block.
+pub fn call(origin: DryRunOrigin, call: Call) -> DryRunEffects {
+	let info = call.get_dispatch_info();
+	let result = call.dispatch(origin.into());
+	let weight = extract_actual_weight(&result, &info);
+
+	effects(Ok(result.map(|_| ()).map_err(|e| e.error)), weight)
+}
+
+fn effects(result: ApplyExtrinsicResult, weight: Weight) -> DryRunEffects {
+	DryRunEffects { result, events: System::events(), weight }
+}
@@ -0,0 +1,117 @@
+//! Genesis and block building helpers to drive the real `Runtime` through `Executive`.
+
+#![allow(dead_code)]
+
+use codec::Encode;
+use node_template_runtime::{
+	AccountId, Address, AuraConfig, Balance, BalancesConfig, BlockNumber, BuildStorage, Call,
+	Executive, GenesisConfig, GrandpaConfig, Hash, Header, Index, Runtime, SignedExtra, SudoConfig,
+	SystemConfig, TimestampCall, UncheckedExtrinsic, SLOT_DURATION, WASM_BINARY,
+};
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
+use sp_runtime::{
+	generic::{Digest, DigestItem, Era, SignedPayload},
+	traits::Header as _,
+};
+
+/// Balance of every endowed account at genesis.
+pub const ENDOWMENT: Balance = 1 << 60;
+
+/// Genesis storage equivalent to the one of `testnet_genesis` on the `dev` chain: `Alice` is
+/// the only authority and the `Sudo` key, `Alice` and `Bob` are endowed.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let endowed_accounts: Vec<AccountId> =
+		vec![Sr25519Keyring::Alice.to_account_id(), Sr25519Keyring::Bob.to_account_id()];
+
+	GenesisConfig {
+		system: SystemConfig {
+			code: WASM_BINARY.unwrap_or_default().to_vec(),
+			changes_trie_config: Default::default(),
+		},
+		balances: BalancesConfig {
+			balances: endowed_accounts.iter().cloned().map(|k| (k, ENDOWMENT)).collect(),
+		},
+		aura: AuraConfig { authorities: vec![Sr25519Keyring::Alice.public().into()] },
+		grandpa: GrandpaConfig { authorities: vec![(Ed25519Keyring::Alice.public().into(), 1)] },
+		sudo: SudoConfig { key: Sr25519Keyring::Alice.to_account_id() },
+	}
+	.build_storage()
+	.expect("genesis config is valid")
+	.into()
+}
+
+/// Initialize block `number` on top of `parent_hash` and apply its timestamp inherent.
+///
+/// Block `n` is authored at Aura slot `n`, so that slots and timestamps agree.
+pub fn initialize_block(number: BlockNumber, parent_hash: Hash) {
+	let slot = Slot::from(number as u64);
+	let digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+	let header = Header::new(number, Default::default(), Default::default(), parent_hash, digest);
+	Executive::initialize_block(&header);
+
+	let timestamp = TimestampCall::set(number as u64 * SLOT_DURATION);
+	let inherent = UncheckedExtrinsic::new_unsigned(Call::Timestamp(timestamp));
+	Executive::apply_extrinsic(inherent)
+		.expect("timestamp inherent is valid")
+		.expect("timestamp inherent is dispatched");
+}
+
+/// Finalize the current block and return its hash.
+pub fn finalize_block() -> Hash {
+	Executive::finalize_block().hash()
+}
+
+/// Build empty blocks until block `number` is initialized.
+///
+/// `parent_hash` is the hash of the last finalized block, block `number` is left open.
+pub fn advance_to(number: BlockNumber, mut parent_hash: Hash) -> Hash {
+	let mut current = frame_system::Pallet::<Runtime>::block_number();
+	while current < number {
+		if current > 0 {
+			parent_hash = finalize_block();
+		}
+		current += 1;
+		initialize_block(current, parent_hash);
+	}
+	parent_hash
+}
+
+/// The full `SignedExtra` of the runtime for the given parameters.
+pub fn signed_extra(nonce: Index, era: Era, tip: Balance) -> SignedExtra {
+	(
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(era),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+	)
+}
+
+/// Sign `call` as `signer` against the current state, the way a wallet would.
+pub fn sign(
+	signer: Sr25519Keyring,
+	nonce: Index,
+	era: Era,
+	tip: Balance,
+	call: Call,
+) -> UncheckedExtrinsic {
+	let extra = signed_extra(nonce, era, tip);
+	let payload = SignedPayload::new(call, extra).expect("additional signed data is available");
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+
+	UncheckedExtrinsic::new_signed(
+		call,
+		Address::Id(signer.to_account_id()),
+		signature.into(),
+		extra,
+	)
+}
+
+/// Free balance of `who`.
+pub fn free_balance(who: Sr25519Keyring) -> Balance {
+	pallet_balances::Pallet::<Runtime>::free_balance(who.to_account_id())
+}
@@ -0,0 +1,65 @@
+use frame_support::weights::GetDispatchInfo;
+use node_template_runtime::{
+	dry_run::{self, DryRunOrigin},
+	pallet_template, Call, Event,
+};
+use sp_keyring::Sr25519Keyring::Alice;
+use sp_runtime::{generic::Era, DispatchError};
+
+mod common;
+
+use common::{advance_to, new_test_ext, sign};
+
+fn cause_error() -> Call {
+	Call::TemplateModule(pallet_template::Call::cause_error())
+}
+
+#[test]
+fn dry_run_extrinsic_reports_events_and_weight() {
+	new_test_ext().execute_with(|| {
+		advance_to(1, Default::default());
+		let call = Call::TemplateModule(pallet_template::Call::do_something(42));
+		let xt = sign(Alice, 0, Era::Immortal, 0, call.clone());
+
+		let effects = dry_run::extrinsic(xt);
+
+		assert_eq!(effects.result, Ok(Ok(())));
+		let stored = Event::TemplateModule(pallet_template::Event::SomethingStored(
+			42,
+			Alice.to_account_id(),
+		));
+		assert!(effects.events.iter().any(|record| record.event == stored));
+		// The base weight of the extrinsic is accounted for as well.
+		assert!(effects.weight > call.get_dispatch_info().weight);
+	});
+}
+
+#[test]
+fn dry_run_call_reports_the_pallet_error() {
+	new_test_ext().execute_with(|| {
+		advance_to(1, Default::default());
+
+		let effects = dry_run::call(DryRunOrigin::Signed(Alice.to_account_id()), cause_error());
+
+		match effects.result {
+			Ok(Err(DispatchError::Module { message: Some("NoneValue"), .. })) => (),
+			other => panic!("expected `NoneValue`, got {:?}", other),
+		}
+		assert!(!effects
+			.events
+			.iter()
+			.any(|record| matches!(record.event, Event::TemplateModule(_))));
+		assert_eq!(effects.weight, cause_error().get_dispatch_info().weight);
+	});
+}
+
+#[test]
+fn dry_run_call_checks_the_origin() {
+	new_test_ext().execute_with(|| {
+		advance_to(1, Default::default());
+
+		let effects = dry_run::call(DryRunOrigin::None, cause_error());
+
+		assert_eq!(effects.result, Ok(Err(DispatchError::BadOrigin)));
+	});
+}
@@ -0,0 +1,164 @@
+use codec::Encode;
+use frame_support::{
+	traits::Get,
+	weights::{constants::WEIGHT_PER_SECOND, DispatchClass, GetDispatchInfo},
+};
+use node_template_runtime::{
+	pallet_template, Address, BalancesCall, BlockWeights, Call, Executive, Runtime,
+	TransactionPayment,
+};
+use sp_keyring::Sr25519Keyring::{Alice, Bob};
+use sp_runtime::{
+	generic::Era,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+mod common;
+
+use common::{advance_to, free_balance, new_test_ext, sign};
+
+fn transfer_to_bob(value: u128) -> Call {
+	Call::Balances(BalancesCall::transfer(Address::Id(Bob.to_account_id()), value))
+}
+
+fn invalid(error: InvalidTransaction) -> TransactionValidityError {
+	TransactionValidityError::Invalid(error)
+}
+
+#[test]
+fn signed_transfer_pays_fees_and_bumps_nonce() {
+	new_test_ext().execute_with(|| {
+		advance_to(1, Default::default());
+		let tip = 1_000;
+		let xt = sign(Alice, 0, Era::Immortal, tip, transfer_to_bob(10_000));
+		let fee =
+			TransactionPayment::compute_fee(xt.encode().len() as u32, &xt.get_dispatch_info(), tip);
+		let (alice, bob) = (free_balance(Alice), free_balance(Bob));
+		let issuance = pallet_balances::Pallet::<Runtime>::total_issuance();
+
+		assert_eq!(Executive::apply_extrinsic(xt), Ok(Ok(())));
+
+		assert_eq!(free_balance(Alice), alice - 10_000 - fee);
+		assert_eq!(free_balance(Bob), bob + 10_000);
+		// Fees, tip included, are burned.
+		assert_eq!(pallet_balances::Pallet::<Runtime>::total_issuance(), issuance - fee);
+		assert_eq!(frame_system::Pallet::<Runtime>::account_nonce(Alice.to_account_id()), 1);
+	});
+}
+
+#[test]
+fn failed_dispatch_still_pays_fees() {
+	new_test_ext().execute_with(|| {
+		advance_to(1, Default::default());
+		let call = Call::TemplateModule(pallet_template::Call::cause_error());
+		let xt = sign(Alice, 0, Era::Immortal, 0, call);
+		let fee =
+			TransactionPayment::compute_fee(xt.encode().len() as u32, &xt.get_dispatch_info(), 0);
+		let alice = free_balance(Alice);
+
+		let outcome = Executive::apply_extrinsic(xt).expect("transaction is valid");
+
+		assert!(outcome.is_err());
+		assert_eq!(free_balance(Alice), alice - fee);
+		assert_eq!(frame_system::Pallet::<Runtime>::account_nonce(Alice.to_account_id()), 1);
+	});
+}
+
+#[test]
+fn nonces_must_be_used_in_order() {
+	new_test_ext().execute_with(|| {
+		advance_to(1, Default::default());
+
+		let future = sign(Alice, 1, Era::Immortal, 0, transfer_to_bob(1_000));
+		assert_eq!(
+			Executive::apply_extrinsic(future.clone()),
+			Err(invalid(InvalidTransaction::Future))
+		);
+
+		let first = sign(Alice, 0, Era::Immortal, 0, transfer_to_bob(1_000));
+		assert_eq!(Executive::apply_extrinsic(first.clone()), Ok(Ok(())));
+		assert_eq!(Executive::apply_extrinsic(first), Err(invalid(InvalidTransaction::Stale)));
+
+		assert_eq!(Executive::apply_extrinsic(future), Ok(Ok(())));
+		assert_eq!(frame_system::Pallet::<Runtime>::account_nonce(Alice.to_account_id()), 2);
+	});
+}
+
+#[test]
+fn mortal_transactions_expire_with_their_era() {
+	new_test_ext().execute_with(|| {
+		let parent_hash = advance_to(2, Default::default());
+		// Valid from block 1 to block 4, signed against the hash of block 1.
+		let era = Era::mortal(4, 1);
+		let in_time = sign(Alice, 0, era, 0, transfer_to_bob(1_000));
+		let too_late = sign(Alice, 1, era, 0, transfer_to_bob(1_000));
+
+		assert_eq!(Executive::apply_extrinsic(in_time), Ok(Ok(())));
+
+		advance_to(6, parent_hash);
+		// The era now points at block 5, the signature no longer matches.
+		assert_eq!(
+			Executive::apply_extrinsic(too_late),
+			Err(invalid(InvalidTransaction::BadProof))
+		);
+	});
+}
+
+#[test]
+fn tampered_transactions_are_rejected() {
+	new_test_ext().execute_with(|| {
+		advance_to(1, Default::default());
+		let xt = sign(Alice, 0, Era::Immortal, 0, transfer_to_bob(1_000));
+		let mut tampered = xt.clone();
+		tampered.function = transfer_to_bob(2_000);
+
+		assert_eq!(
+			Executive::apply_extrinsic(tampered),
+			Err(invalid(InvalidTransaction::BadProof))
+		);
+		assert_eq!(Executive::apply_extrinsic(xt), Ok(Ok(())));
+	});
+}
+
+#[test]
+fn block_weights_limit_normal_transactions() {
+	new_test_ext().execute_with(|| {
+		advance_to(1, Default::default());
+		let weights = BlockWeights::get();
+		let normal = weights.get(DispatchClass::Normal);
+		let base = normal.base_extrinsic;
+		let max_extrinsic = normal.max_extrinsic.expect("normal extrinsics are limited");
+		let heavy = |weight| {
+			let remark = Call::System(frame_system::Call::remark(vec![]));
+			Call::Sudo(pallet_sudo::Call::sudo_unchecked_weight(Box::new(remark), weight))
+		};
+
+		let too_heavy = sign(Alice, 0, Era::Immortal, 0, heavy(max_extrinsic - base + 1));
+		assert_eq!(
+			Executive::apply_extrinsic(too_heavy),
+			Err(invalid(InvalidTransaction::ExhaustsResources)),
+		);
+
+		let heaviest = sign(Alice, 0, Era::Immortal, 0, heavy(max_extrinsic - base));
+		assert_eq!(Executive::apply_extrinsic(heaviest), Ok(Ok(())));
+
+		// Another one does not fit in the normal class of the block anymore.
+		let again = sign(Alice, 1, Era::Immortal, 0, heavy(max_extrinsic - base));
+		assert_eq!(
+			Executive::apply_extrinsic(again),
+			Err(invalid(InvalidTransaction::ExhaustsResources)),
+		);
+		// 2 seconds of compute per block.
+		assert_eq!(weights.max_block, 2 * WEIGHT_PER_SECOND);
+	});
+}
+
+#[test]
+fn block_hashes_are_recorded_on_initialization() {
+	new_test_ext().execute_with(|| {
+		let parent_hash = advance_to(3, Default::default());
+
+		assert_eq!(frame_system::Pallet::<Runtime>::block_number(), 3);
+		assert_eq!(frame_system::Pallet::<Runtime>::block_hash(2), parent_hash);
+	});
+}